@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::error::ReporterError;
+use crate::state::{write_atomic, State};
+
+const BACKUP_DIR: &str = "backups";
+const ROLLING_PREFIX: &str = "rolling-";
+const DAILY_PREFIX: &str = "daily-";
+const MIGRATION_PREFIX: &str = "migration-";
+const ROLLING_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+const DAILY_FORMAT: &str = "%Y-%m-%d";
+
+/// How many of the most recent pre-save snapshots are kept.
+pub const KEEP_ROLLING: usize = 10;
+/// How many days a daily snapshot is kept.
+pub const KEEP_DAILY_DAYS: i64 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    Rolling,
+    Daily,
+    Migration,
+}
+
+impl SnapshotKind {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Rolling => "Перед сохранением",
+            Self::Daily => "Ежедневная",
+            Self::Migration => "Перед обновлением формата",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub kind: SnapshotKind,
+    pub created: NaiveDateTime,
+}
+
+impl Snapshot {
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        if let Some(stamp) = stem.strip_prefix(ROLLING_PREFIX) {
+            let created = NaiveDateTime::parse_from_str(stamp, ROLLING_FORMAT).ok()?;
+            return Some(Self { kind: SnapshotKind::Rolling, created, path });
+        }
+        if let Some(stamp) = stem.strip_prefix(MIGRATION_PREFIX) {
+            // migration-v<version>-<timestamp>
+            let (_, stamp) = stamp.split_once('-')?;
+            let created = NaiveDateTime::parse_from_str(stamp, ROLLING_FORMAT).ok()?;
+            return Some(Self { kind: SnapshotKind::Migration, created, path });
+        }
+        if let Some(stamp) = stem.strip_prefix(DAILY_PREFIX) {
+            let created = NaiveDate::parse_from_str(stamp, DAILY_FORMAT).ok()?.and_hms_opt(0, 0, 0)?;
+            return Some(Self { kind: SnapshotKind::Daily, created, path });
+        }
+        None
+    }
+
+    pub fn load(&self) -> Result<State, ReporterError> {
+        State::load_from(&self.path)
+    }
+}
+
+pub fn backup_dir(state_file: &Path) -> PathBuf {
+    state_file.with_file_name(BACKUP_DIR)
+}
+
+/// Copies the current state file into the backup directory before it gets
+/// overwritten, then drops snapshots that are past their retention.
+pub fn snapshot_before_save(state_file: &Path) -> Result<(), ReporterError> {
+    let contents = match std::fs::read(state_file) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    snapshot_rolling(state_file, &contents)?;
+    snapshot_daily(state_file, || Ok(contents))
+}
+
+/// Keeps `contents`, the data as it was before a full rewrite, as a pre-save snapshot.
+pub fn snapshot_rolling(state_file: &Path, contents: &[u8]) -> Result<(), ReporterError> {
+    let now = chrono::Local::now().naive_local();
+    let rolling = backup_dir(state_file).join(format!("{}{}.json", ROLLING_PREFIX, now.format(ROLLING_FORMAT)));
+    write_atomic(&rolling, |file| std::io::Write::write_all(file, contents))
+}
+
+/// Writes today's daily snapshot unless there already is one. The first save of
+/// the day preserves how the data looked when the day began.
+pub fn snapshot_daily<F>(state_file: &Path, contents: F) -> Result<(), ReporterError>
+where
+    F: FnOnce() -> Result<Vec<u8>, ReporterError>,
+{
+    let dir = backup_dir(state_file);
+    let now = chrono::Local::now().naive_local();
+    let daily = dir.join(format!("{}{}.json", DAILY_PREFIX, now.format(DAILY_FORMAT)));
+    if !daily.exists() {
+        let contents = contents()?;
+        write_atomic(&daily, |file| std::io::Write::write_all(file, &contents))?;
+    }
+
+    prune(&dir, now)
+}
+
+/// Keeps the data as it was before a schema upgrade; these copies are never pruned.
+pub fn snapshot_before_migration(state_file: &Path, from_version: u32, contents: &[u8]) -> Result<(), ReporterError> {
+    let now = chrono::Local::now().naive_local();
+    let target = backup_dir(state_file).join(format!(
+        "{}v{}-{}.json",
+        MIGRATION_PREFIX,
+        from_version,
+        now.format(ROLLING_FORMAT)
+    ));
+    write_atomic(&target, |file| std::io::Write::write_all(file, contents))
+}
+
+fn prune(dir: &Path, now: NaiveDateTime) -> Result<(), ReporterError> {
+    let daily_cutoff = now - Duration::days(KEEP_DAILY_DAYS);
+    let mut rolling_seen = 0;
+    for snapshot in list_in(dir)? {
+        let expired = match snapshot.kind {
+            SnapshotKind::Rolling => {
+                rolling_seen += 1;
+                rolling_seen > KEEP_ROLLING
+            }
+            SnapshotKind::Daily => snapshot.created < daily_cutoff,
+            SnapshotKind::Migration => false,
+        };
+        if expired {
+            std::fs::remove_file(&snapshot.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Lists snapshots of `state_file`, newest first.
+pub fn list_snapshots(state_file: &Path) -> Result<Vec<Snapshot>, ReporterError> {
+    list_in(&backup_dir(state_file))
+}
+
+fn list_in(dir: &Path) -> Result<Vec<Snapshot>, ReporterError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        if let Some(snapshot) = Snapshot::from_path(entry?.path()) {
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created));
+    Ok(snapshots)
+}
@@ -0,0 +1,96 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReporterError;
+use crate::paths;
+use crate::state::CarryMode;
+use crate::status::StatusDef;
+
+/// Settings read from `config.json` in the data directory. Every field has a
+/// default, so the file only needs the ones that differ.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub carry_over: CarryOverConfig,
+    /// Empty means the built-in statuses.
+    pub statuses: Vec<StatusDef>,
+    pub sprint: SprintConfig,
+    /// Days off besides weekends, which the stand-up report skips.
+    pub holidays: Vec<NaiveDate>,
+    pub api: ApiConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CarryOverConfig {
+    /// Carry unfinished tasks over to today on the first launch of the day.
+    pub on_startup: bool,
+    pub mode: CarryMode,
+}
+
+/// For the "this sprint" report preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SprintConfig {
+    pub length_days: u32,
+    /// First day of any sprint, to count the others from. Without it the
+    /// sprint is the last `length_days` days up to today.
+    pub start: Option<NaiveDate>,
+}
+
+impl Default for SprintConfig {
+    fn default() -> Self {
+        Self {
+            length_days: 14,
+            start: None,
+        }
+    }
+}
+
+/// The HTTP API on localhost, off unless enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Clients send it as `Authorization: Bearer <token>`. The API won't start without one.
+    pub token: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8734,
+            token: String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// A missing file means all defaults.
+    pub fn load() -> Result<Self, ReporterError> {
+        match std::fs::read_to_string(paths::config_file()?) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let json = r#"{"sprint": {"start": "2026-02-16"}, "holidays": ["2026-03-09"]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.sprint.length_days, 14);
+        assert_eq!(config.sprint.start, NaiveDate::from_ymd_opt(2026, 2, 16));
+        assert_eq!(config.holidays, [NaiveDate::from_ymd_opt(2026, 3, 9).unwrap()]);
+        assert!(config.statuses.is_empty());
+        assert!(!config.carry_over.on_startup);
+        assert!(!config.api.enabled);
+    }
+}
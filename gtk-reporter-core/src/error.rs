@@ -0,0 +1,66 @@
+#[derive(Debug, thiserror::Error)]
+pub enum ReporterError {
+    #[error("IO ошибка: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("JSON ошибка: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Ошибка базы данных: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+
+    #[error("Дата не найдена")]
+    DateNotFound,
+
+    #[error("Строка не найдена")]
+    RowNotFound,
+
+    #[error("Текст задачи не может быть пустым")]
+    EmptyText,
+
+    #[error("Неверная дата «{0}», используйте формат ГГГГ-ММ-ДД")]
+    InvalidDate(String),
+
+    #[error("Не удалось определить каталог для данных, укажите его через --data-dir")]
+    NoDataDir,
+
+    #[error("Данные открыты только для чтения")]
+    ReadOnly,
+
+    #[error("Ежедневник уже запущен, закройте его и повторите")]
+    AlreadyRunning,
+
+    #[error("Данные уже хранятся в формате {0}")]
+    AlreadyInFormat(&'static str),
+
+    #[error("Файл создан более новой версией приложения (формат {found}, поддерживается до {supported})")]
+    UnsupportedSchema { found: u32, supported: u32 },
+
+    #[error("Неизвестный статус «{0}»")]
+    UnknownStatus(String),
+
+    #[error("Начало периода {from} позже его конца {to}")]
+    InvalidRange { from: String, to: String },
+
+    #[error("Ошибка в шаблоне: {0}")]
+    Template(String),
+
+    #[error("Недопустимое имя шаблона «{0}»")]
+    TemplateName(String),
+
+    #[error("Изменения сохранены, но не записаны в журнал: {0}")]
+    Journal(String),
+
+    #[error("Статусы из настроек не применены: уже используются другие")]
+    StatusesInUse,
+
+    #[error("Для HTTP API задайте в настройках api.token")]
+    NoApiToken,
+}
+
+impl ReporterError {
+    /// The state file does not exist yet (first launch), as opposed to being unreadable.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::IoError(e) if e.kind() == std::io::ErrorKind::NotFound)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::error::ReporterError;
+use crate::journal;
+use crate::state::{CarryMode, Row, RowChange, RowDiff, State};
+
+/// A user action on the tasks.
+#[derive(Debug, Clone)]
+pub enum Command {
+    Apply(RowChange),
+    Delete { date: String, id: u32 },
+    CarryOver { from: String, to: String, mode: CarryMode },
+}
+
+/// Everything one command changed, undone and redone together.
+type Step = Vec<RowDiff>;
+
+type Listener = Box<dyn Fn(&[RowDiff])>;
+
+/// Undo and redo stacks for the current session.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// Counts changes to the top of the undo stack.
+    version: u64,
+    listeners: Vec<Listener>,
+}
+
+impl std::fmt::Debug for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("History")
+            .field("undo", &self.undo)
+            .field("redo", &self.redo)
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `listener` with the rows each later command, undo or redo changed.
+    pub fn add_listener<F: Fn(&[RowDiff]) + 'static>(&mut self, listener: F) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Identifies the latest step: `undo` reverts the command that left this
+    /// version only while it stays the same.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn notify(&mut self, diffs: &[RowDiff]) {
+        self.version += 1;
+        if !diffs.is_empty() {
+            self.listeners.iter().for_each(|listener| listener(diffs));
+        }
+    }
+
+    /// Runs `command` as one saved transaction and remembers how to revert it.
+    /// Returns the tasks it created or changed, as they are afterwards. Even
+    /// with `ReporterError::Journal` the command is done and can be undone.
+    pub fn execute(&mut self, state: &mut State, command: Command) -> Result<Vec<Row>, ReporterError> {
+        let (rows, step) = state.save_change(|s| match command {
+            Command::Apply(change) => Ok(vec![s.apply(change)?]),
+            Command::Delete { date, id } => {
+                s.delete_row(date, id)?;
+                Ok(Vec::new())
+            }
+            Command::CarryOver { from, to, mode } => s.carry_over(&from, &to, mode),
+        })?;
+
+        let journaled = journal::record(&step);
+        if !step.is_empty() {
+            self.notify(&step);
+            self.undo.push(step);
+            self.redo.clear();
+        }
+        journaled.map(|()| rows)
+    }
+
+    /// Reverts the latest command. Returns false when there is nothing to undo.
+    pub fn undo(&mut self, state: &mut State) -> Result<bool, ReporterError> {
+        let Some(step) = self.undo.last() else {
+            return Ok(false);
+        };
+        let ((), diffs) = state.save_change(|s| {
+            step.iter()
+                .try_for_each(|row| s.restore_row(row.id, row.before.clone()))
+        })?;
+        self.notify(&diffs);
+        self.redo.extend(self.undo.pop());
+        journal::record(&diffs).map(|()| true)
+    }
+
+    /// Repeats the latest undone command. Returns false when there is nothing to redo.
+    pub fn redo(&mut self, state: &mut State) -> Result<bool, ReporterError> {
+        let Some(step) = self.redo.last() else {
+            return Ok(false);
+        };
+        let ((), diffs) = state.save_change(|s| {
+            step.iter()
+                .try_for_each(|row| s.restore_row(row.id, row.after.clone()))
+        })?;
+        self.notify(&diffs);
+        self.undo.extend(self.redo.pop());
+        journal::record(&diffs).map(|()| true)
+    }
+
+    /// Forgets everything, for when the tasks were replaced from outside.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.version += 1;
+    }
+}
+
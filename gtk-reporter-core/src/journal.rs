@@ -0,0 +1,213 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReporterError;
+use crate::paths;
+use crate::state::RowDiff;
+use crate::status::Status;
+
+/// What happened to a task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Created {
+        date: String,
+        text: String,
+        status: Status,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        carried_from: Option<u32>,
+    },
+    TextChanged { from: String, to: String },
+    StatusChanged { from: Status, to: Status },
+    BlockedChanged { blocked: bool },
+    Moved { from: String, to: String },
+    Deleted { date: String },
+}
+
+/// One line of the journal file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Local>,
+    pub id: u32,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Events describing `diffs`, in their order.
+pub fn diff(diffs: &[RowDiff], at: DateTime<Local>) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut push = |id, event| entries.push(Entry { at, id, event });
+    for RowDiff { id, before, after } in diffs.iter().cloned() {
+        match (before, after) {
+            (None, Some((date, row))) => push(
+                id,
+                Event::Created {
+                    date,
+                    text: row.text,
+                    status: row.status,
+                    carried_from: row.carried_from,
+                },
+            ),
+            (Some((date, _)), None) => push(id, Event::Deleted { date }),
+            (Some((old_date, old)), Some((new_date, new))) => {
+                if old.text != new.text {
+                    push(id, Event::TextChanged { from: old.text, to: new.text });
+                }
+                if old.status != new.status {
+                    push(id, Event::StatusChanged { from: old.status, to: new.status });
+                }
+                if old.blocked != new.blocked {
+                    push(id, Event::BlockedChanged { blocked: new.blocked });
+                }
+                if old_date != new_date {
+                    push(id, Event::Moved { from: old_date, to: new_date });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    entries
+}
+
+/// Adds `entries` to the end of the journal in one write.
+pub fn append(path: &Path, entries: &[Entry]) -> Result<(), ReporterError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut lines = Vec::new();
+    for entry in entries {
+        serde_json::to_writer(&mut lines, entry)?;
+        lines.push(b'\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&lines)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Journals the tasks a save changed. They are already saved at this point,
+/// so a failure comes back as `ReporterError::Journal`.
+pub fn record(diffs: &[RowDiff]) -> Result<(), ReporterError> {
+    let entries = diff(diffs, Local::now());
+    paths::journal_file()
+        .and_then(|path| append(&path, &entries))
+        .map_err(|err| ReporterError::Journal(err.to_string()))
+}
+
+/// Every recorded event of the tasks in `ids`, oldest first. Lines that can't
+/// be read, such as one cut short by a crash, are skipped.
+pub fn task_history(path: &Path, ids: &[u32]) -> Result<Vec<Entry>, ReporterError> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str::<Entry>(&line?)
+            && ids.contains(&entry.id)
+        {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{} дн. {} ч", days, hours)
+    } else if hours > 0 {
+        format!("{} ч {} мин", hours, minutes)
+    } else {
+        format!("{} мин", minutes)
+    }
+}
+
+/// Human-readable lines for a task's history. Status changes also say how long
+/// the task stayed in the previous status.
+pub fn timeline(entries: &[Entry]) -> Vec<String> {
+    let mut status_since: Option<DateTime<Local>> = None;
+    entries
+        .iter()
+        .map(|entry| {
+            let what = match &entry.event {
+                Event::Created { date, status, carried_from: Some(origin), .. } => {
+                    format!("перенесена с задачи #{} на {} со статусом «{}»", origin, date, status.label())
+                }
+                Event::Created { date, status, .. } => {
+                    status_since = Some(entry.at);
+                    format!("создана на {} со статусом «{}»", date, status.label())
+                }
+                Event::TextChanged { .. } => "изменён текст".to_string(),
+                Event::StatusChanged { from, to } => {
+                    let stayed = status_since
+                        .map(|since| format!(" (в статусе «{}» {})", from.label(), format_duration(entry.at - since)))
+                        .unwrap_or_default();
+                    status_since = Some(entry.at);
+                    format!("статус «{}» → «{}»{}", from.label(), to.label(), stayed)
+                }
+                Event::BlockedChanged { blocked: true } => "отмечена как заблокированная".to_string(),
+                Event::BlockedChanged { blocked: false } => "снята отметка о блокировке".to_string(),
+                Event::Moved { from, to } => format!("перенесена с {} на {}", from, to),
+                Event::Deleted { date } => format!("удалена с {}", date),
+            };
+            format!("{} — {}", entry.at.format("%Y-%m-%d %H:%M"), what)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{RowChange, State, diff_rows};
+    use crate::status;
+    use chrono::TimeZone;
+
+    #[test]
+    fn diff_and_timeline_follow_a_task_through_testing() {
+        let mut state = State::new();
+        let created = state.clone();
+        let row = state
+            .apply(RowChange::Create {
+                date: "2026-02-19".to_string(),
+                text: "Задача".to_string(),
+                status: Status::new(status::WORKING),
+                blocked: false,
+            })
+            .unwrap();
+        let start = Local.with_ymd_and_hms(2026, 2, 19, 10, 0, 0).unwrap();
+        let mut entries = diff(&diff_rows(&created, &state), start);
+
+        for (status, hours) in [(Status::new(status::TESTING), 2), (Status::new(status::READY), 50)] {
+            let before = state.clone();
+            state
+                .apply(RowChange::Update {
+                    date: "2026-02-19".to_string(),
+                    id: row.id,
+                    text: None,
+                    status: Some(status),
+                    blocked: None,
+                    new_date: None,
+                })
+                .unwrap();
+            entries.extend(diff(&diff_rows(&before, &state), start + chrono::Duration::hours(hours)));
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        append(&path, &entries).unwrap();
+        let read = task_history(&path, &[row.id]).unwrap();
+        assert_eq!(read, entries);
+
+        let lines = timeline(&read);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("(в статусе «В работе» 2 ч 0 мин)"), "{}", lines[1]);
+        assert!(lines[2].contains("(в статусе «Передал в тестирование» 2 дн. 0 ч)"), "{}", lines[2]);
+    }
+}
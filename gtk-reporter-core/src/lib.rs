@@ -0,0 +1,40 @@
+//! Everything gtk-reporter knows about tasks apart from showing them: the
+//! `State` model and its validation, JSON and SQLite storage with backups and
+//! the journal, undo history, merging outside changes, settings, report
+//! templates, statistics and search.
+//!
+//! Call `paths::init` once before anything that reads or writes files, and
+//! `status::init` with the configured statuses before any status is used;
+//! without it the built-in statuses apply.
+//!
+//! ```no_run
+//! use gtk_reporter_core::config::Config;
+//! use gtk_reporter_core::error::ReporterError;
+//! use gtk_reporter_core::state::State;
+//! use gtk_reporter_core::{paths, status};
+//!
+//! fn main() -> Result<(), ReporterError> {
+//!     // `None` is the default data directory, the same as the app's
+//!     paths::init(None)?;
+//!     status::init(Config::load()?.statuses)?;
+//!     let state = State::load()?;
+//!     print!("{}", state.generate_report("2026-02-19"));
+//!     Ok(())
+//! }
+//! ```
+
+pub mod backup;
+pub mod config;
+pub mod error;
+pub mod history;
+pub mod journal;
+pub mod merge;
+pub mod paths;
+pub mod report;
+pub mod search;
+pub mod sqlite;
+pub mod state;
+pub mod stats;
+pub mod status;
+pub mod storage;
+pub mod template;
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::state::{Row, State};
+
+/// A row together with the date it is filed under.
+pub type Placed = (String, Row);
+
+/// A row that was changed differently on both sides since `base`.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub id: u32,
+    pub base: Option<Placed>,
+    pub local: Option<Placed>,
+    pub remote: Option<Placed>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+/// Outcome of a three-way merge; conflicting rows are left to the caller.
+#[derive(Debug)]
+pub struct Merge {
+    pub state: State,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Applies one side's version to every conflicting row.
+    pub fn resolve(mut self, side: Side) -> State {
+        for conflict in &self.conflicts {
+            let chosen = match side {
+                Side::Local => &conflict.local,
+                Side::Remote => &conflict.remote,
+            };
+            remove_row(&mut self.state, conflict.id);
+            if let Some((date, row)) = chosen.clone() {
+                self.state.rows.entry(date).or_default().push(row);
+            }
+        }
+        self.state
+    }
+}
+
+fn remove_row(state: &mut State, id: u32) {
+    state.rows.retain(|_, rows| {
+        rows.retain(|r| r.id != id);
+        !rows.is_empty()
+    });
+}
+
+/// Flattens rows in date order, keeping the order of rows within a day.
+fn placed_rows(state: &State) -> Vec<Placed> {
+    let mut dates: Vec<&String> = state.rows.keys().collect();
+    dates.sort();
+    dates
+        .into_iter()
+        .flat_map(|date| state.rows[date].iter().map(move |row| (date.clone(), row.clone())))
+        .collect()
+}
+
+/// Three-way merge of rows keyed by `Row.id`. Rows changed on one side only are
+/// taken from that side; rows changed on both sides are reported as conflicts
+/// and keep the local version until resolved.
+pub fn merge(base: &State, local: &State, remote: &State) -> Merge {
+    let base_rows: HashMap<u32, Placed> = placed_rows(base).into_iter().map(|p| (p.1.id, p)).collect();
+    let local_list = placed_rows(local);
+    let remote_list = placed_rows(remote);
+    let local_rows: HashMap<u32, Placed> = local_list.iter().cloned().map(|p| (p.1.id, p)).collect();
+    let remote_rows: HashMap<u32, Placed> = remote_list.iter().cloned().map(|p| (p.1.id, p)).collect();
+
+    let mut max_id = local.max_id.max(remote.max_id);
+    let mut merged: Vec<Placed> = Vec::new();
+    let mut conflicts = Vec::new();
+
+    // Local order first, then whatever only the other side has
+    let mut seen = HashSet::new();
+    let ids: Vec<u32> = local_list
+        .iter()
+        .chain(remote_list.iter())
+        .chain(placed_rows(base).iter())
+        .map(|(_, row)| row.id)
+        .filter(|id| seen.insert(*id))
+        .collect();
+
+    for id in ids {
+        let b = base_rows.get(&id);
+        let l = local_rows.get(&id);
+        let r = remote_rows.get(&id);
+
+        match (b, l, r) {
+            _ if l == r || r == b => merged.extend(l.cloned()),
+            _ if l == b => merged.extend(r.cloned()),
+            (None, Some(l), Some(r)) => {
+                // Both sides created a task with the same id: these are two different tasks
+                merged.push(r.clone());
+                max_id += 1;
+                let (date, mut row) = l.clone();
+                row.id = max_id;
+                merged.push((date, row));
+            }
+            _ => {
+                conflicts.push(Conflict {
+                    id,
+                    base: b.cloned(),
+                    local: l.cloned(),
+                    remote: r.cloned(),
+                });
+                merged.extend(l.cloned());
+            }
+        }
+    }
+
+    let mut rows: HashMap<String, Vec<Row>> = HashMap::new();
+    for (date, row) in merged {
+        rows.entry(date).or_default().push(row);
+    }
+
+    let mut state = State::new();
+    state.max_id = max_id;
+    state.rows = rows;
+    state.cur_date = local.cur_date.clone();
+    state.read_only = local.read_only;
+    Merge { state, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RowChange;
+    use crate::status::{self, Status};
+
+    fn state_with(rows: &[(&str, u32, &str)]) -> State {
+        let mut state = State::new();
+        for (date, id, text) in rows {
+            state.rows.entry(date.to_string()).or_default().push(Row::new(*id, text.to_string()));
+            state.max_id = state.max_id.max(*id);
+        }
+        state
+    }
+
+    #[test]
+    fn one_sided_changes_are_combined() {
+        let base = state_with(&[("2026-02-19", 1, "a"), ("2026-02-19", 2, "b")]);
+        let mut local = base.clone();
+        local
+            .apply(RowChange::Update {
+                date: "2026-02-19".to_string(),
+                id: 1,
+                text: Some("a, правка".to_string()),
+                status: None,
+                blocked: None,
+                new_date: None,
+            })
+            .unwrap();
+        let mut remote = base.clone();
+        remote
+            .apply(RowChange::Update {
+                date: "2026-02-19".to_string(),
+                id: 2,
+                text: None,
+                status: Some(Status::new(status::READY)),
+                blocked: None,
+                new_date: Some("2026-02-20".to_string()),
+            })
+            .unwrap();
+
+        let merged = merge(&base, &local, &remote);
+
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.state.get_row("2026-02-19", 1).unwrap().text, "a, правка");
+        assert_eq!(merged.state.get_row("2026-02-20", 2).unwrap().status, Status::new(status::READY));
+    }
+
+    #[test]
+    fn same_id_added_on_both_sides_keeps_both() {
+        let base = state_with(&[("2026-02-19", 1, "a")]);
+        let local = state_with(&[("2026-02-19", 1, "a"), ("2026-02-19", 2, "здесь")]);
+        let remote = state_with(&[("2026-02-19", 1, "a"), ("2026-02-19", 2, "в файле")]);
+
+        let merged = merge(&base, &local, &remote);
+
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.state.max_id, 3);
+        assert_eq!(merged.state.get_row("2026-02-19", 2).unwrap().text, "в файле");
+        assert_eq!(merged.state.get_row("2026-02-19", 3).unwrap().text, "здесь");
+    }
+
+    #[test]
+    fn edits_on_both_sides_conflict() {
+        let base = state_with(&[("2026-02-19", 1, "a")]);
+        let local = state_with(&[("2026-02-19", 1, "здесь")]);
+        let remote = state_with(&[("2026-02-19", 1, "в файле")]);
+
+        let merged = merge(&base, &local, &remote);
+        assert_eq!(merged.conflicts.len(), 1);
+
+        let resolved = merged.resolve(Side::Remote);
+        assert_eq!(resolved.get_row("2026-02-19", 1).unwrap().text, "в файле");
+    }
+}
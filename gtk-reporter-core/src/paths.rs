@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::ReporterError;
+
+/// Environment variable that overrides the data directory, same as `--data-dir`.
+pub const DATA_DIR_ENV: &str = "GTK_REPORTER_DATA_DIR";
+
+const APP_DIR: &str = "gtk-reporter";
+const LEGACY_DIR: &str = ".gtk-reporter";
+const STATE_FILE: &str = "gtk-reporter.json";
+const DATABASE_FILE: &str = "gtk-reporter.db";
+const JOURNAL_FILE: &str = "gtk-reporter.journal.jsonl";
+const CONFIG_FILE: &str = "config.json";
+const LAST_LAUNCH_FILE: &str = "last-launch";
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Picks the data directory for this run: `--data-dir`, then `GTK_REPORTER_DATA_DIR`,
+/// then `$XDG_DATA_HOME/gtk-reporter`. Only the default location takes over
+/// data from the legacy `~/.gtk-reporter`.
+pub fn init(data_dir_arg: Option<PathBuf>) -> Result<(), ReporterError> {
+    let from_env = std::env::var_os(DATA_DIR_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from);
+    let dir = match data_dir_arg.or(from_env) {
+        Some(dir) => dir,
+        None => {
+            let dir = default_data_dir()?;
+            migrate_legacy(&dir)?;
+            dir
+        }
+    };
+    let _ = DATA_DIR.set(dir);
+    Ok(())
+}
+
+pub fn data_dir() -> Result<PathBuf, ReporterError> {
+    match DATA_DIR.get() {
+        Some(dir) => Ok(dir.clone()),
+        None => default_data_dir(),
+    }
+}
+
+pub fn state_file() -> Result<PathBuf, ReporterError> {
+    Ok(data_dir()?.join(STATE_FILE))
+}
+
+pub fn database_file() -> Result<PathBuf, ReporterError> {
+    Ok(data_dir()?.join(DATABASE_FILE))
+}
+
+pub fn journal_file() -> Result<PathBuf, ReporterError> {
+    Ok(data_dir()?.join(JOURNAL_FILE))
+}
+
+pub fn config_file() -> Result<PathBuf, ReporterError> {
+    Ok(data_dir()?.join(CONFIG_FILE))
+}
+
+/// Holds the date of the latest launch, for things done once a day.
+pub fn last_launch_file() -> Result<PathBuf, ReporterError> {
+    Ok(data_dir()?.join(LAST_LAUNCH_FILE))
+}
+
+fn default_data_dir() -> Result<PathBuf, ReporterError> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_DIR))
+        .ok_or(ReporterError::NoDataDir)
+}
+
+/// Moves everything from `~/.gtk-reporter` into `dir` unless `dir` already has data.
+fn migrate_legacy(dir: &Path) -> Result<(), ReporterError> {
+    let Some(legacy) = dirs::home_dir().map(|home| home.join(LEGACY_DIR)) else {
+        return Ok(());
+    };
+    if legacy == dir || !legacy.join(STATE_FILE).exists() || dir.join(STATE_FILE).exists() {
+        return Ok(());
+    }
+    move_dir_contents(&legacy, dir)?;
+    // Leave the old directory alone if something we don't know about is still in it
+    let _ = std::fs::remove_dir(&legacy);
+    Ok(())
+}
+
+fn move_dir_contents(from: &Path, to: &Path) -> Result<(), ReporterError> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if std::fs::rename(entry.path(), &target).is_ok() {
+            continue;
+        }
+        // Renaming fails across filesystems, fall back to copying
+        if entry.file_type()?.is_dir() {
+            move_dir_contents(&entry.path(), &target)?;
+            std::fs::remove_dir(entry.path())?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,557 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::config::SprintConfig;
+use crate::error::ReporterError;
+use crate::state::{Row, State, validate_date, write_atomic};
+use crate::status::Status;
+use crate::template::{Context, Template, Value};
+
+/// What a report can be saved or copied as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Laid out by the picked template.
+    Text,
+    Markdown,
+    Html,
+}
+
+impl Format {
+    /// By file extension; anything unknown is plain text.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+            Some("md" | "markdown") => Self::Markdown,
+            Some("html" | "htm") => Self::Html,
+            _ => Self::Text,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Statuses present in `rows`, configured ones in their order and then any the
+/// config no longer has, each with its tasks in the order the day lists them.
+pub fn group_by_status(rows: &[Row]) -> Vec<(Status, Vec<&Row>)> {
+    let mut statuses: Vec<&Status> = rows.iter().map(|r| &r.status).collect();
+    statuses.sort_by_key(|status| status.order());
+    statuses.dedup();
+
+    statuses
+        .into_iter()
+        .map(|status| {
+            let status_rows: Vec<&Row> = rows.iter().filter(|r| r.status == *status).collect();
+            (status.clone(), status_rows)
+        })
+        .collect()
+}
+
+fn text(value: impl ToString) -> Value {
+    Value::Text(value.to_string())
+}
+
+/// A list whose items know whether they come first or last, for separators.
+fn list(mut items: Vec<Context>) -> Value {
+    let len = items.len();
+    for (i, item) in items.iter_mut().enumerate() {
+        item.insert("first", Value::Flag(i == 0));
+        item.insert("last", Value::Flag(i + 1 == len));
+    }
+    Value::List(items)
+}
+
+/// What report templates see for one day:
+/// `date`, `title`, `count` and `statuses`, each with `id`, `label`, `count` and `rows`,
+/// each of those with `id`, `number` and `text`.
+pub fn day_context(state: &State, date: &str) -> Context {
+    let rows = state.get_rows_for_date(date);
+    let statuses = group_by_status(&rows)
+        .into_iter()
+        .map(|(status, rows)| {
+            let items = rows
+                .iter()
+                .enumerate()
+                .map(|(i, row)| Context::from([("id", text(row.id)), ("number", text(i + 1)), ("text", text(&row.text))]))
+                .collect();
+            Context::from([
+                ("id", text(status.id())),
+                ("label", text(status.label())),
+                ("count", text(rows.len())),
+                ("rows", list(items)),
+            ])
+        })
+        .collect();
+    Context::from([
+        ("date", text(date)),
+        ("title", text(format!("Отчет {}", date))),
+        ("count", text(rows.len())),
+        ("statuses", list(statuses)),
+    ])
+}
+
+/// Ready-made periods for range reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangePreset {
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    Sprint,
+}
+
+impl RangePreset {
+    pub const ALL: [Self; 4] = [Self::ThisWeek, Self::LastWeek, Self::ThisMonth, Self::Sprint];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::ThisWeek => "Эта неделя",
+            Self::LastWeek => "Прошлая неделя",
+            Self::ThisMonth => "Этот месяц",
+            Self::Sprint => "Текущий спринт",
+        }
+    }
+
+    /// First and last day of the period around `today`. Weeks start on Monday.
+    pub fn range(self, today: NaiveDate, sprint: &SprintConfig) -> (NaiveDate, NaiveDate) {
+        let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+        match self {
+            Self::ThisWeek => (monday, monday + Duration::days(6)),
+            Self::LastWeek => (monday - Duration::days(7), monday - Duration::days(1)),
+            Self::ThisMonth => {
+                let first = today.with_day(1).unwrap_or(today);
+                let next = first.checked_add_months(chrono::Months::new(1)).unwrap_or(first);
+                (first, next - Duration::days(1))
+            }
+            Self::Sprint => {
+                let length = i64::from(sprint.length_days.max(1));
+                let first = match sprint.start {
+                    Some(start) => start + Duration::days((today - start).num_days().div_euclid(length) * length),
+                    None => today - Duration::days(length - 1),
+                };
+                (first, first + Duration::days(length - 1))
+            }
+        }
+    }
+}
+
+/// How a range report lays out tasks within a status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeGrouping {
+    /// Under each day they were on.
+    ByDay,
+    /// Once per task text, with the status of its latest day.
+    Merged,
+}
+
+/// What report templates see for a period. On top of what `day_context` gives,
+/// there are `from` and `to`; statuses have `days`, each with `date` and `rows`,
+/// when grouped by day; rows have `date`, and `times` when merged from several days.
+pub fn range_context(state: &State, from: &str, to: &str, grouping: RangeGrouping) -> Result<Context, ReporterError> {
+    validate_date(from)?;
+    validate_date(to)?;
+    if from > to {
+        return Err(ReporterError::InvalidRange {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
+    let mut dates: Vec<String> = state
+        .get_all_dates()
+        .into_iter()
+        .filter(|date| date.as_str() >= from && date.as_str() <= to)
+        .collect();
+    dates.sort();
+
+    let mut rows: Vec<Row> = Vec::new();
+    let mut row_dates: HashMap<u32, String> = HashMap::new();
+    let mut times: HashMap<u32, usize> = HashMap::new();
+    let mut merged: HashMap<String, usize> = HashMap::new();
+    for date in &dates {
+        for row in state.get_rows_for_date(date) {
+            if grouping == RangeGrouping::Merged {
+                let key = row.text.trim().to_lowercase();
+                if let Some(&slot) = merged.get(&key) {
+                    // The latest day wins; it keeps the count of the ones before
+                    let seen = times.remove(&rows[slot].id).unwrap_or(1);
+                    times.insert(row.id, seen + 1);
+                    row_dates.insert(row.id, date.clone());
+                    rows[slot] = row;
+                    continue;
+                }
+                merged.insert(key, rows.len());
+            }
+            row_dates.insert(row.id, date.clone());
+            rows.push(row);
+        }
+    }
+
+    let row_item = |i: usize, row: &Row| {
+        let mut item = Context::from([
+            ("id", text(row.id)),
+            ("number", text(i + 1)),
+            ("text", text(&row.text)),
+            ("date", text(&row_dates[&row.id])),
+        ]);
+        if let Some(&count) = times.get(&row.id).filter(|&&count| count > 1) {
+            item.insert("times", text(count));
+        }
+        item
+    };
+
+    let statuses = group_by_status(&rows)
+        .into_iter()
+        .map(|(status, rows)| {
+            let mut ctx = Context::from([
+                ("id", text(status.id())),
+                ("label", text(status.label())),
+                ("count", text(rows.len())),
+                ("rows", list(rows.iter().enumerate().map(|(i, row)| row_item(i, row)).collect())),
+            ]);
+            if grouping == RangeGrouping::ByDay {
+                let mut by_day: BTreeMap<&str, Vec<&Row>> = BTreeMap::new();
+                for row in &rows {
+                    by_day.entry(row_dates[&row.id].as_str()).or_default().push(row);
+                }
+                let days = by_day
+                    .into_iter()
+                    .map(|(date, rows)| {
+                        Context::from([
+                            ("date", text(date)),
+                            ("count", text(rows.len())),
+                            ("rows", list(rows.iter().enumerate().map(|(i, row)| row_item(i, row)).collect())),
+                        ])
+                    })
+                    .collect();
+                ctx.insert("days", list(days));
+            }
+            ctx
+        })
+        .collect();
+
+    let period = format!("{} — {}", from, to);
+    Ok(Context::from([
+        ("date", text(&period)),
+        ("title", text(format!("Отчет {}", period))),
+        ("from", text(from)),
+        ("to", text(to)),
+        ("count", text(rows.len())),
+        ("statuses", list(statuses)),
+    ]))
+}
+
+/// The working day before `day`, skipping weekends and `holidays`.
+pub fn previous_working_day(day: NaiveDate, holidays: &[NaiveDate]) -> NaiveDate {
+    let mut previous = day - Duration::days(1);
+    while previous.weekday().num_days_from_monday() >= 5 || holidays.contains(&previous) {
+        previous -= Duration::days(1);
+    }
+    previous
+}
+
+/// What the stand-up layouts see: `date` and `yesterday_date`, and the task
+/// lists `yesterday`, `today` and `blockers` with `text` and `status`.
+/// Yesterday is the previous working day with all its tasks, today has the
+/// unfinished ones, and unfinished blocked tasks of either day go to the
+/// blockers instead.
+pub fn standup_context(state: &State, today: &str, holidays: &[NaiveDate]) -> Result<Context, ReporterError> {
+    validate_date(today)?;
+    let day = NaiveDate::parse_from_str(today, "%Y-%m-%d").map_err(|_| ReporterError::InvalidDate(today.to_string()))?;
+    let yesterday = previous_working_day(day, holidays).format("%Y-%m-%d").to_string();
+
+    let ordered = |date: &str| -> Vec<Row> {
+        let rows = state.get_rows_for_date(date);
+        group_by_status(&rows)
+            .into_iter()
+            .flat_map(|(_, rows)| rows.into_iter().cloned())
+            .collect()
+    };
+    let (yesterday_blocked, yesterday_rows): (Vec<Row>, Vec<Row>) = ordered(&yesterday)
+        .into_iter()
+        .partition(|row| row.is_blocked() && !row.status.is_done());
+    let (today_blocked, today_rows): (Vec<Row>, Vec<Row>) = ordered(today)
+        .into_iter()
+        .filter(|row| !row.status.is_done())
+        .partition(Row::is_blocked);
+
+    // A task carried over to today is the same blocker as yesterday's
+    let mut blockers = today_blocked;
+    for row in yesterday_blocked {
+        let key = row.text.trim().to_lowercase();
+        if !blockers.iter().any(|blocker| blocker.text.trim().to_lowercase() == key) {
+            blockers.push(row);
+        }
+    }
+
+    let items = |rows: Vec<Row>| {
+        list(
+            rows.iter()
+                .map(|row| Context::from([("id", text(row.id)), ("text", text(&row.text)), ("status", text(row.status.label()))]))
+                .collect(),
+        )
+    };
+    Ok(Context::from([
+        ("date", text(today)),
+        ("title", text(format!("Стендап {}", today))),
+        ("yesterday_date", text(&yesterday)),
+        ("yesterday", items(yesterday_rows)),
+        ("today", items(today_rows)),
+        ("blockers", items(blockers)),
+    ]))
+}
+
+const STANDUP: &str = "Вчера ({{yesterday_date}}):\n\
+    {{#yesterday}}• {{text}} — {{status}}\n{{/yesterday}}{{^yesterday}}—\n{{/yesterday}}\n\
+    Сегодня:\n{{#today}}• {{text}}\n{{/today}}{{^today}}—\n{{/today}}\n\
+    Блокеры:\n{{#blockers}}• {{text}}\n{{/blockers}}{{^blockers}}нет\n{{/blockers}}";
+
+const STANDUP_HTML: &str = "<h3>Вчера ({{yesterday_date}})</h3>\n\
+    {{#yesterday}}{{#first}}<ul>\n{{/first}}<li>{{text}} — {{status}}</li>\n{{#last}}</ul>\n{{/last}}{{/yesterday}}\
+    {{^yesterday}}<p>—</p>\n{{/yesterday}}\
+    <h3>Сегодня</h3>\n\
+    {{#today}}{{#first}}<ul>\n{{/first}}<li>{{text}}</li>\n{{#last}}</ul>\n{{/last}}{{/today}}\
+    {{^today}}<p>—</p>\n{{/today}}\
+    <h3>Блокеры</h3>\n\
+    {{#blockers}}{{#first}}<ul>\n{{/first}}<li>{{text}}</li>\n{{#last}}</ul>\n{{/last}}{{/blockers}}\
+    {{^blockers}}<p>нет</p>\n{{/blockers}}";
+
+/// "Вчера / Сегодня / Блокеры" as plain text.
+pub fn standup_text(ctx: &Context) -> String {
+    render_builtin(STANDUP, ctx, str::to_string)
+}
+
+/// The same as `standup_text`, as an HTML fragment for the clipboard.
+pub fn standup_html(ctx: &Context) -> String {
+    render_builtin(STANDUP_HTML, ctx, escape_html)
+}
+
+// Periods also get totals per status and, when grouped by day, a subheading per day
+const MARKDOWN: &str = "# {{title}}\n\n\
+    {{#statuses}}## {{label}}{{#from}} ({{count}}){{/from}}\n\n\
+    {{#days}}### {{date}}\n\n{{#rows}}- {{text}}\n{{/rows}}\n{{/days}}\
+    {{^days}}{{#rows}}- {{text}}{{#times}} (×{{times}}){{/times}}\n{{/rows}}\n{{/days}}{{/statuses}}\
+    {{^statuses}}Нет задач.\n{{/statuses}}";
+
+const HTML: &str = "<h1>{{title}}</h1>\n\
+    {{#statuses}}<h2>{{label}}{{#from}} ({{count}}){{/from}}</h2>\n\
+    {{#days}}<h3>{{date}}</h3>\n<ul>\n{{#rows}}<li>{{text}}</li>\n{{/rows}}</ul>\n{{/days}}\
+    {{^days}}<ul>\n{{#rows}}<li>{{text}}{{#times}} (×{{times}}){{/times}}</li>\n{{/rows}}</ul>\n{{/days}}{{/statuses}}\
+    {{^statuses}}<p>Нет задач.</p>\n{{/statuses}}";
+
+fn escape_markdown(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_builtin(source: &str, ctx: &Context, escape: fn(&str) -> String) -> String {
+    Template::new("", source)
+        .render_escaped(ctx, escape)
+        .unwrap_or_else(|err| err.to_string())
+}
+
+/// Headings per status and a bullet list of their tasks.
+pub fn markdown(ctx: &Context) -> String {
+    render_builtin(MARKDOWN, ctx, escape_markdown)
+}
+
+/// The same as `markdown`, as an HTML fragment for the clipboard.
+pub fn html(ctx: &Context) -> String {
+    render_builtin(HTML, ctx, escape_html)
+}
+
+/// A standalone page for saving to a file.
+pub fn html_document(ctx: &Context) -> String {
+    let title = match ctx.get("title") {
+        Some(Value::Text(title)) => escape_html(title),
+        _ => String::new(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title,
+        html(ctx)
+    )
+}
+
+/// Writes the report in the format the file name asks for, with `text` as the
+/// plain text version.
+pub fn save(path: &Path, ctx: &Context, text: &str) -> Result<(), ReporterError> {
+    let contents = match Format::from_path(path) {
+        Format::Text => text.to_string(),
+        Format::Markdown => markdown(ctx),
+        Format::Html => html_document(ctx),
+    };
+    write_atomic(path, |file| file.write_all(contents.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RowChange;
+    use crate::state::tests::add;
+    use crate::config::SprintConfig;
+    use crate::status;
+    use crate::template::Template;
+
+    #[test]
+    fn standard_template_keeps_the_old_layout() {
+        let mut state = State::new();
+        assert_eq!(state.generate_report("2026-02-19"), "Отчет 2026-02-19\n\nНет задач за эту дату.");
+
+        add(&mut state, "2026-02-19", "Первая");
+        let id = add(&mut state, "2026-02-19", "Вторая");
+        state
+            .apply(RowChange::Update {
+                date: "2026-02-19".to_string(),
+                id,
+                text: None,
+                status: Some(Status::new(status::READY)),
+                blocked: None,
+                new_date: None,
+            })
+            .unwrap();
+        assert_eq!(
+            state.generate_report("2026-02-19"),
+            "Отчет 2026-02-19\n\n=== В работе ===\n• Первая\n\n=== Готово ===\n• Вторая\n\n"
+        );
+
+        // Tasks saved before status times were kept stay where they are
+        add(&mut state, "2026-02-19", "Третья");
+        state.rows.get_mut("2026-02-19").unwrap().last_mut().unwrap().status_changed_at = None;
+        assert_eq!(
+            state.generate_report("2026-02-19"),
+            "Отчет 2026-02-19\n\n=== В работе ===\n• Первая\n• Третья\n\n=== Готово ===\n• Вторая\n\n"
+        );
+
+        let short = Template::new("t", "{{#statuses}}{{label}} ({{count}}): {{#rows}}{{number}}. {{text}}{{/rows}}{{^last}}; {{/last}}{{/statuses}}");
+        assert_eq!(
+            short.render(&day_context(&state, "2026-02-19")).unwrap(),
+            "В работе (2): 1. Первая2. Третья; Готово (1): 1. Вторая"
+        );
+    }
+
+    #[test]
+    fn markdown_and_html_escape_task_text() {
+        let mut state = State::new();
+        add(&mut state, "2026-02-19", "Поправить <b> & *звёздочки*");
+        let ctx = day_context(&state, "2026-02-19");
+
+        assert_eq!(
+            markdown(&ctx),
+            "# Отчет 2026-02-19\n\n## В работе\n\n- Поправить \\<b\\> & \\*звёздочки\\*\n\n"
+        );
+        assert_eq!(
+            html(&ctx),
+            "<h1>Отчет 2026-02-19</h1>\n<h2>В работе</h2>\n<ul>\n<li>Поправить &lt;b&gt; &amp; *звёздочки*</li>\n</ul>\n"
+        );
+        assert_eq!(Format::from_path(Path::new("week.MD")), Format::Markdown);
+        assert_eq!(Format::from_path(Path::new("report")), Format::Text);
+    }
+
+    #[test]
+    fn range_reports_group_by_day_or_merge_repeats() {
+        let mut state = State::new();
+        add(&mut state, "2026-02-16", "Ревью");
+        add(&mut state, "2026-02-18", "ревью");
+        add(&mut state, "2026-02-18", "Релиз");
+        add(&mut state, "2026-02-23", "Не в этой неделе");
+        let standard = Template::standard();
+
+        let ctx = range_context(&state, "2026-02-16", "2026-02-22", RangeGrouping::ByDay).unwrap();
+        assert_eq!(
+            standard.render(&ctx).unwrap(),
+            "Отчет 2026-02-16 — 2026-02-22\n\n=== В работе (3) ===\n\
+             2026-02-16:\n• Ревью\n2026-02-18:\n• ревью\n• Релиз\n\n"
+        );
+
+        let ctx = range_context(&state, "2026-02-16", "2026-02-22", RangeGrouping::Merged).unwrap();
+        assert_eq!(
+            standard.render(&ctx).unwrap(),
+            "Отчет 2026-02-16 — 2026-02-22\n\n=== В работе (2) ===\n• ревью (×2)\n• Релиз\n\n"
+        );
+
+        assert!(matches!(
+            range_context(&state, "2026-02-22", "2026-02-16", RangeGrouping::Merged),
+            Err(ReporterError::InvalidRange { .. })
+        ));
+    }
+
+    #[test]
+    fn presets_cover_the_expected_days() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let today = date("2026-02-19");
+        let sprint = SprintConfig {
+            length_days: 14,
+            start: Some(date("2026-01-05")),
+        };
+        assert_eq!(RangePreset::ThisWeek.range(today, &sprint), (date("2026-02-16"), date("2026-02-22")));
+        assert_eq!(RangePreset::LastWeek.range(today, &sprint), (date("2026-02-09"), date("2026-02-15")));
+        assert_eq!(RangePreset::ThisMonth.range(today, &sprint), (date("2026-02-01"), date("2026-02-28")));
+        assert_eq!(RangePreset::Sprint.range(today, &sprint), (date("2026-02-16"), date("2026-03-01")));
+        let rolling = SprintConfig::default();
+        assert_eq!(RangePreset::Sprint.range(today, &rolling), (date("2026-02-06"), date("2026-02-19")));
+    }
+
+    #[test]
+    fn standup_skips_the_weekend_and_collects_blockers() {
+        let friday = "2026-02-20";
+        let monday = "2026-02-23";
+        let mut state = State::new();
+        add(&mut state, friday, "Ревью");
+        let waiting = add(&mut state, friday, "Ждём доступы");
+        add(&mut state, monday, "Релиз");
+        let unblocked = add(&mut state, friday, "Дождались ответа");
+        let done = add(&mut state, monday, "Уже готово");
+        let changes = [
+            (friday, waiting, None, Some(true)),
+            (friday, unblocked, Some(status::READY), Some(true)),
+            (monday, done, Some(status::READY), None),
+        ];
+        for (date, id, status, blocked) in changes {
+            state
+                .apply(RowChange::Update {
+                    date: date.to_string(),
+                    id,
+                    text: None,
+                    status: status.map(Status::new),
+                    blocked,
+                    new_date: None,
+                })
+                .unwrap();
+        }
+
+        let ctx = standup_context(&state, monday, &[]).unwrap();
+        assert_eq!(
+            standup_text(&ctx),
+            "Вчера (2026-02-20):\n• Ревью — В работе\n• Дождались ответа — Готово\n\nСегодня:\n• Релиз\n\nБлокеры:\n• Ждём доступы\n"
+        );
+
+        // With Friday off, Thursday had nothing
+        let holiday = NaiveDate::from_ymd_opt(2026, 2, 20).unwrap();
+        let ctx = standup_context(&state, monday, &[holiday]).unwrap();
+        assert_eq!(
+            standup_text(&ctx),
+            "Вчера (2026-02-19):\n—\n\nСегодня:\n• Релиз\n\nБлокеры:\nнет\n"
+        );
+    }
+}
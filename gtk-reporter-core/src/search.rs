@@ -0,0 +1,208 @@
+use std::ops::Range;
+
+use crate::state::{Row, State};
+
+/// Characters of context kept on each side of the first match.
+const CONTEXT_CHARS: usize = 40;
+
+/// What to look for. Empty text finds every task the filters let through.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub text: String,
+    /// A status id.
+    pub status: Option<String>,
+    /// Inclusive bounds, `YYYY-MM-DD`.
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// The part of a task's text around its matches, with their byte ranges in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub text: String,
+    pub matches: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub date: String,
+    pub row: Row,
+    pub snippet: Snippet,
+}
+
+/// `text` in lower case, each char with the byte range of the one it came from.
+fn fold(text: &str) -> Vec<(char, Range<usize>)> {
+    text.char_indices()
+        .flat_map(|(start, c)| {
+            let end = start + c.len_utf8();
+            c.to_lowercase().map(move |lower| (lower, start..end))
+        })
+        .collect()
+}
+
+/// Where the lower case `term` appears in the folded text, as byte ranges of the original.
+fn find_all(folded: &[(char, Range<usize>)], term: &[char]) -> Vec<Range<usize>> {
+    if term.is_empty() {
+        return Vec::new();
+    }
+    folded
+        .windows(term.len())
+        .filter(|window| window.iter().map(|(c, _)| c).eq(term))
+        .map(|window| window[0].1.start..window[term.len() - 1].1.end)
+        .collect()
+}
+
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Cuts `text` down to the first match and some context around it, on one line.
+fn snippet(text: &str, matches: &[Range<usize>]) -> Snippet {
+    let (first_start, first_end) = matches.first().map_or((0, 0), |first| (first.start, first.end));
+    let start = text[..first_start]
+        .char_indices()
+        .rev()
+        .take(CONTEXT_CHARS)
+        .last()
+        .map_or(first_start, |(i, _)| i);
+    let end = text[first_end..]
+        .char_indices()
+        .nth(CONTEXT_CHARS)
+        .map_or(text.len(), |(i, _)| first_end + i);
+
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < text.len() { "…" } else { "" };
+    // Single bytes for single bytes, so the ranges stay valid
+    let body = text[start..end].replace(['\n', '\r', '\t'], " ");
+    let shift = |i: usize| i - start + prefix.len();
+    Snippet {
+        text: format!("{}{}{}", prefix, body, suffix),
+        matches: matches
+            .iter()
+            .filter(|range| range.start < end)
+            .map(|range| shift(range.start)..shift(range.end.min(end)))
+            .collect(),
+    }
+}
+
+fn lower_case(text: &str) -> String {
+    // Char by char, the same as `fold`
+    text.chars().flat_map(char::to_lowercase).collect()
+}
+
+/// Tasks matching `query`, newest date first, at most `limit` of them, and
+/// how many matched in all. Every word of the text has to appear in the
+/// task, in any case.
+pub fn search(state: &State, query: &Query, limit: usize) -> (Vec<Hit>, usize) {
+    let terms: Vec<String> = query.text.split_whitespace().map(lower_case).collect();
+
+    let mut dates: Vec<&String> = state
+        .rows
+        .keys()
+        .filter(|date| query.from.as_ref().is_none_or(|from| *date >= from))
+        .filter(|date| query.to.as_ref().is_none_or(|to| *date <= to))
+        .collect();
+    dates.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut hits = Vec::new();
+    let mut total = 0;
+    for date in dates {
+        for row in &state.rows[date] {
+            if query.status.as_deref().is_some_and(|status| row.status.id() != status) {
+                continue;
+            }
+            let text = lower_case(&row.text);
+            if !terms.iter().all(|term| text.contains(term.as_str())) {
+                continue;
+            }
+            total += 1;
+            // Finding where exactly is slower, so only for the hits shown
+            if hits.len() < limit {
+                let folded = fold(&row.text);
+                let matches = terms
+                    .iter()
+                    .flat_map(|term| find_all(&folded, &term.chars().collect::<Vec<_>>()))
+                    .collect();
+                hits.push(Hit {
+                    date: date.clone(),
+                    row: row.clone(),
+                    snippet: snippet(&row.text, &merge(matches)),
+                });
+            }
+        }
+    }
+    (hits, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::add;
+    use crate::state::RowChange;
+    use crate::status::{self, Status};
+
+    fn highlighted(snippet: &Snippet) -> Vec<&str> {
+        snippet.matches.iter().map(|range| &snippet.text[range.clone()]).collect()
+    }
+
+    #[test]
+    fn finds_every_word_in_any_case_and_filters() {
+        let mut state = State::new();
+        add(&mut state, "2026-02-16", "Починить ВХОД через почту");
+        let id = add(&mut state, "2026-02-19", "Проверить вход после починки");
+        add(&mut state, "2026-02-19", "Разобрать почту");
+        state
+            .apply(RowChange::Update {
+                date: "2026-02-19".to_string(),
+                id,
+                text: None,
+                status: Some(Status::new(status::READY)),
+                blocked: None,
+                new_date: None,
+            })
+            .unwrap();
+
+        let query = Query {
+            text: "вход почин".to_string(),
+            ..Query::default()
+        };
+        let (hits, total) = search(&state, &query, 10);
+        assert_eq!(total, 2);
+        assert_eq!(hits.iter().map(|hit| hit.date.as_str()).collect::<Vec<_>>(), ["2026-02-19", "2026-02-16"]);
+        assert_eq!(highlighted(&hits[0].snippet), ["вход", "почин"]);
+        assert_eq!(highlighted(&hits[1].snippet), ["Почин", "ВХОД"]);
+
+        let ready = Query {
+            status: Some(status::READY.to_string()),
+            ..query.clone()
+        };
+        assert_eq!(search(&state, &ready, 10).1, 1);
+        let before = Query {
+            to: Some("2026-02-18".to_string()),
+            ..query.clone()
+        };
+        assert_eq!(search(&state, &before, 10).0[0].date, "2026-02-16");
+        assert_eq!(search(&state, &query, 1).0.len(), 1);
+        assert_eq!(search(&state, &Query::default(), 10).1, 3);
+    }
+
+    #[test]
+    fn snippets_keep_context_around_the_first_match() {
+        let text = format!("{}\nнужное слово{}", "а".repeat(100), "б".repeat(100));
+        let folded = fold(&text);
+        let term: Vec<char> = "нужное".chars().collect();
+        let snippet = snippet(&text, &find_all(&folded, &term));
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+        assert_eq!(snippet.text.chars().count(), 2 + CONTEXT_CHARS * 2 + "нужное".chars().count());
+        assert!(!snippet.text.contains('\n'));
+        assert_eq!(highlighted(&snippet), ["нужное"]);
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+
+use crate::backup;
+use crate::error::ReporterError;
+use crate::state::{Mutation, Row, SCHEMA_VERSION, State};
+use crate::storage::Storage;
+
+// Rows are kept as serialized JSON next to the columns that are queried, so
+// schema migrations stay in one place and work the same for both backends.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS rows (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS rows_by_date ON rows (date, position);
+";
+
+/// Tasks in a SQLite database, written one row per change inside a transaction.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self, ReporterError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            conn,
+        })
+    }
+
+    /// Reads the database into the same document shape as the JSON file.
+    fn read_document(&self) -> Result<serde_json::Value, ReporterError> {
+        let schema_version = read_meta(&self.conn, "schema_version")?.unwrap_or(SCHEMA_VERSION);
+        let max_id = read_meta(&self.conn, "max_id")?.unwrap_or(0);
+
+        let mut rows: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+        let mut stmt = self.conn.prepare("SELECT date, data FROM rows ORDER BY date, position")?;
+        let mut query = stmt.query([])?;
+        while let Some(record) = query.next()? {
+            let date: String = record.get(0)?;
+            let data: String = record.get(1)?;
+            rows.entry(date).or_default().push(serde_json::from_str(&data)?);
+        }
+
+        Ok(serde_json::json!({
+            "schema_version": schema_version,
+            "max_id": max_id,
+            "rows": rows,
+            "cur_date": "",
+        }))
+    }
+
+    fn export_json(&self) -> Result<Vec<u8>, ReporterError> {
+        Ok(serde_json::to_vec(&self.read_document()?)?)
+    }
+
+    fn write_all(&self, tx: &Transaction, state: &State) -> Result<(), ReporterError> {
+        tx.execute("DELETE FROM rows", [])?;
+        for (date, rows) in &state.rows {
+            for (position, row) in rows.iter().enumerate() {
+                insert_row(tx, date, position as i64, row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Mutations are replayed against the final state, so rows are looked up by
+    /// id wherever they ended up; rows removed later are skipped.
+    fn apply(&self, tx: &Transaction, state: &State, mutation: &Mutation) -> Result<(), ReporterError> {
+        match mutation {
+            Mutation::Add { id, .. } => {
+                if let Some((date, row)) = find_row(state, *id) {
+                    insert_row(tx, date, next_position(tx, date)?, row)?;
+                }
+            }
+            Mutation::Edit { id, .. } => {
+                if let Some((_, row)) = find_row(state, *id) {
+                    tx.execute(
+                        "UPDATE rows SET text = ?2, data = ?3 WHERE id = ?1",
+                        params![row.id, row.text, serde_json::to_string(row)?],
+                    )?;
+                }
+            }
+            Mutation::Move { id, .. } => {
+                if let Some((date, row)) = find_row(state, *id) {
+                    tx.execute(
+                        "UPDATE rows SET date = ?2, position = ?3, data = ?4 WHERE id = ?1",
+                        params![row.id, date, next_position(tx, date)?, serde_json::to_string(row)?],
+                    )?;
+                }
+            }
+            Mutation::Delete { id, .. } => {
+                tx.execute("DELETE FROM rows WHERE id = ?1", params![id])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn in_transaction<F>(&self, state: &State, write: F) -> Result<(), ReporterError>
+    where
+        F: FnOnce(&Transaction) -> Result<(), ReporterError>,
+    {
+        backup::snapshot_daily(&self.path, || self.export_json())?;
+        let tx = self.conn.unchecked_transaction()?;
+        write(&tx)?;
+        write_meta(&tx, state)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn read_meta(conn: &Connection, key: &str) -> Result<Option<u32>, ReporterError> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |r| r.get(0))
+        .optional()?)
+}
+
+fn write_meta(tx: &Transaction, state: &State) -> Result<(), ReporterError> {
+    let upsert = "INSERT INTO meta (key, value) VALUES (?1, ?2)
+        ON CONFLICT (key) DO UPDATE SET value = excluded.value";
+    tx.execute(upsert, params!["schema_version", SCHEMA_VERSION])?;
+    tx.execute(upsert, params!["max_id", state.max_id])?;
+    Ok(())
+}
+
+fn insert_row(tx: &Transaction, date: &str, position: i64, row: &Row) -> Result<(), ReporterError> {
+    tx.execute(
+        "INSERT OR REPLACE INTO rows (id, date, position, text, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![row.id, date, position, row.text, serde_json::to_string(row)?],
+    )?;
+    Ok(())
+}
+
+fn next_position(tx: &Transaction, date: &str) -> Result<i64, ReporterError> {
+    Ok(tx.query_row(
+        "SELECT COALESCE(MAX(position) + 1, 0) FROM rows WHERE date = ?1",
+        params![date],
+        |r| r.get(0),
+    )?)
+}
+
+fn find_row(state: &State, id: u32) -> Option<(&str, &Row)> {
+    state.rows.iter().find_map(|(date, rows)| {
+        rows.iter().find(|r| r.id == id).map(|row| (date.as_str(), row))
+    })
+}
+
+impl Storage for SqliteStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&self) -> Result<State, ReporterError> {
+        let doc = self.read_document()?;
+        let (s, found) = State::from_document(doc.clone())?;
+        if found < SCHEMA_VERSION {
+            backup::snapshot_before_migration(&self.path, found, &serde_json::to_vec(&doc)?)?;
+            self.save(&s)?;
+        }
+        Ok(s)
+    }
+
+    fn peek(&self) -> Result<State, ReporterError> {
+        State::from_document(self.read_document()?).map(|(s, _)| s)
+    }
+
+    fn save(&self, state: &State) -> Result<(), ReporterError> {
+        // Replaces everything, so keep a copy as JSON saves do; a new database has nothing to keep
+        if read_meta(&self.conn, "max_id")?.is_some() {
+            backup::snapshot_rolling(&self.path, &self.export_json()?)?;
+        }
+        self.in_transaction(state, |tx| self.write_all(tx, state))
+    }
+
+    fn add(&self, state: &State, date: &str, id: u32) -> Result<(), ReporterError> {
+        let mutation = Mutation::Add { date: date.to_string(), id };
+        self.in_transaction(state, |tx| self.apply(tx, state, &mutation))
+    }
+
+    fn edit(&self, state: &State, date: &str, id: u32) -> Result<(), ReporterError> {
+        let mutation = Mutation::Edit { date: date.to_string(), id };
+        self.in_transaction(state, |tx| self.apply(tx, state, &mutation))
+    }
+
+    fn move_row(&self, state: &State, from: &str, to: &str, id: u32) -> Result<(), ReporterError> {
+        let mutation = Mutation::Move {
+            from: from.to_string(),
+            to: to.to_string(),
+            id,
+        };
+        self.in_transaction(state, |tx| self.apply(tx, state, &mutation))
+    }
+
+    fn delete(&self, state: &State, date: &str, id: u32) -> Result<(), ReporterError> {
+        let mutation = Mutation::Delete { date: date.to_string(), id };
+        self.in_transaction(state, |tx| self.apply(tx, state, &mutation))
+    }
+
+    fn commit(&self, state: &State, mutations: Option<&[Mutation]>) -> Result<(), ReporterError> {
+        let Some(mutations) = mutations else {
+            return self.save(state);
+        };
+        self.in_transaction(state, |tx| {
+            mutations
+                .iter()
+                .try_for_each(|mutation| self.apply(tx, state, mutation))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::add;
+    use crate::state::RowChange;
+    use crate::status::{self, Status};
+
+    fn storage_in(dir: &tempfile::TempDir) -> SqliteStorage {
+        SqliteStorage::open(&dir.path().join("gtk-reporter.db")).unwrap()
+    }
+
+    #[test]
+    fn committed_mutations_match_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+
+        let mut state = State::new();
+        storage.save(&state).unwrap();
+        state.mark_persisted();
+
+        let first = add(&mut state, "2026-02-19", "Первая");
+        let second = add(&mut state, "2026-02-19", "Вторая");
+        let third = add(&mut state, "2026-02-19", "Третья");
+        let ready = RowChange::Update {
+            date: "2026-02-19".to_string(),
+            id: first,
+            text: None,
+            status: Some(Status::new(status::READY)),
+            blocked: None,
+            new_date: None,
+        };
+        state.apply(ready).unwrap();
+        state.move_row("2026-02-19".to_string(), second, "2026-02-20".to_string()).unwrap();
+        state.delete_row("2026-02-19".to_string(), third).unwrap();
+        state.save_with(&storage).unwrap();
+
+        let loaded = storage.load().unwrap();
+        assert!(loaded.same_data(&state));
+    }
+
+    #[test]
+    fn full_save_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+
+        let mut old = State::new();
+        add(&mut old, "2026-02-19", "Старая");
+        storage.save(&old).unwrap();
+
+        let mut new = State::new();
+        add(&mut new, "2026-03-01", "Новая");
+        add(&mut new, "2026-03-01", "Ещё одна");
+        storage.save(&new).unwrap();
+
+        let loaded = storage.load().unwrap();
+        assert!(loaded.same_data(&new));
+        assert_eq!(loaded.get_rows_for_date("2026-03-01")[1].text, "Ещё одна");
+
+        // Only the rewrite of existing data leaves a copy of it
+        let snapshots = backup::list_snapshots(storage.path()).unwrap();
+        let rolling: Vec<_> = snapshots.iter().filter(|s| s.kind == backup::SnapshotKind::Rolling).collect();
+        assert_eq!(rolling.len(), 1);
+        assert_eq!(rolling[0].load().unwrap().get_rows_for_date("2026-02-19")[0].text, "Старая");
+    }
+}
@@ -0,0 +1,900 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReporterError;
+use crate::journal;
+use crate::report;
+use crate::status::{self, Status};
+use crate::storage::{self, Storage};
+use crate::template::Template;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Row {
+    pub id: u32,
+    pub text: String,
+    pub status: Status,
+    // Unknown for tasks saved before timestamps were kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_changed_at: Option<DateTime<Local>>,
+    /// The task this one was copied from when carried over to another day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carried_from: Option<u32>,
+    /// Waiting on something outside the task, for the stand-up blockers.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
+}
+
+impl Row {
+    pub fn new(id: u32, text: String) -> Self {
+        Self {
+            id,
+            text,
+            status: Status::initial(),
+            created_at: None,
+            updated_at: None,
+            status_changed_at: None,
+            carried_from: None,
+            blocked: false,
+        }
+    }
+
+    /// Flagged as blocked or in a status that means it.
+    pub fn is_blocked(&self) -> bool {
+        self.blocked || self.status.is_blocked()
+    }
+
+    /// When anything about the task last changed, as far as is known.
+    pub fn last_change(&self) -> Option<DateTime<Local>> {
+        self.updated_at.or(self.created_at)
+    }
+}
+
+/// Whether carried over tasks leave their old day or stay there as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CarryMode {
+    #[default]
+    Move,
+    Copy,
+}
+
+/// Ways to order the tasks of a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskOrder {
+    /// As they were added, the order reports use.
+    Manual,
+    /// Newest first.
+    Created,
+    /// Most recently changed first.
+    Updated,
+    /// Longest in their current status first.
+    StatusChanged,
+}
+
+impl TaskOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "manual" => Some(Self::Manual),
+            "created" => Some(Self::Created),
+            "updated" => Some(Self::Updated),
+            "status" => Some(Self::StatusChanged),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::StatusChanged => "status",
+        }
+    }
+
+    /// The timestamp this order sorts by.
+    pub fn timestamp(self, row: &Row) -> Option<DateTime<Local>> {
+        match self {
+            Self::Manual | Self::Updated => row.last_change(),
+            Self::Created => row.created_at,
+            Self::StatusChanged => row.status_changed_at.or(row.created_at),
+        }
+    }
+}
+
+/// Writes a file through a temporary sibling that is fsynced and then renamed
+/// over `path`, so a crash at any point leaves either the old or the new file.
+pub fn write_atomic<F>(path: &Path, write: F) -> Result<(), ReporterError>
+where
+    F: FnOnce(&mut File) -> std::io::Result<()>,
+{
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    std::fs::create_dir_all(&dir)?;
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = File::create(&tmp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    });
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(err.into());
+    }
+
+    // Persist the rename itself; directories can't be opened for this on Windows
+    #[cfg(unix)]
+    File::open(&dir)?.sync_all()?;
+    Ok(())
+}
+
+pub const SCHEMA_VERSION: u32 = 2;
+
+type Migration = fn(&mut serde_json::Value) -> Result<(), ReporterError>;
+
+/// Step `i` upgrades a document from schema version `i` to `i + 1`.
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// Files written before versioning only lack the `schema_version` field.
+fn migrate_v0_to_v1(_doc: &mut serde_json::Value) -> Result<(), ReporterError> {
+    Ok(())
+}
+
+/// Statuses are stored as config ids instead of enum variant names.
+fn migrate_v1_to_v2(doc: &mut serde_json::Value) -> Result<(), ReporterError> {
+    let Some(rows) = doc.get_mut("rows").and_then(serde_json::Value::as_object_mut) else {
+        return Ok(());
+    };
+    for row in rows.values_mut().filter_map(serde_json::Value::as_array_mut).flatten() {
+        let id = row.get("status").and_then(serde_json::Value::as_str).and_then(status::legacy_id);
+        if let (Some(id), Some(obj)) = (id, row.as_object_mut()) {
+            obj.insert("status".to_string(), id.into());
+        }
+    }
+    Ok(())
+}
+
+/// Upgrades `doc` to the current schema and returns the version it had.
+fn migrate(doc: &mut serde_json::Value) -> Result<u32, ReporterError> {
+    let found = doc
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .map_or(0, |v| v as u32);
+    if found > SCHEMA_VERSION {
+        return Err(ReporterError::UnsupportedSchema {
+            found,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    for (version, step) in MIGRATIONS.iter().enumerate().skip(found as usize) {
+        step(doc)?;
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert("schema_version".to_string(), (version as u32 + 1).into());
+        }
+    }
+    Ok(found)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub max_id: u32,
+    pub rows: HashMap<String, Vec<Row>>,
+    pub cur_date: String,
+    #[serde(skip)]
+    pub read_only: bool,
+    /// Mutations since the last save; `None` when storage has to be rewritten as a whole.
+    #[serde(skip)]
+    pending: Option<Vec<Mutation>>,
+}
+
+/// A change to a single row, as recorded for the storage backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+    Add { date: String, id: u32 },
+    Edit { date: String, id: u32 },
+    Move { from: String, to: String, id: u32 },
+    Delete { date: String, id: u32 },
+}
+
+/// A change to one task, applied as a whole by [`State::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowChange {
+    Create {
+        date: String,
+        text: String,
+        status: Status,
+        blocked: bool,
+    },
+    /// Fields left as `None` keep their current value.
+    Update {
+        date: String,
+        id: u32,
+        text: Option<String>,
+        status: Option<Status>,
+        blocked: Option<bool>,
+        new_date: Option<String>,
+    },
+}
+
+fn validate_text(text: &str) -> Result<String, ReporterError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ReporterError::EmptyText);
+    }
+    Ok(text.to_string())
+}
+
+/// How one task looked before and after a change, with the date it was filed under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowDiff {
+    pub id: u32,
+    pub before: Option<(String, Row)>,
+    pub after: Option<(String, Row)>,
+}
+
+/// Every row by id with its date, in one pass.
+fn index(state: &State) -> HashMap<u32, (&String, &Row)> {
+    state
+        .rows
+        .iter()
+        .flat_map(|(date, rows)| rows.iter().map(move |row| (row.id, (date, row))))
+        .collect()
+}
+
+/// The tasks that differ between `before` and `after`, ordered by id.
+pub fn diff_rows(before: &State, after: &State) -> Vec<RowDiff> {
+    let (old, new) = (index(before), index(after));
+    let owned = |placed: Option<&(&String, &Row)>| placed.map(|(date, row)| ((*date).clone(), (*row).clone()));
+    let mut diffs: Vec<RowDiff> = old
+        .keys()
+        .chain(new.keys().filter(|id| !old.contains_key(id)))
+        .filter(|id| old.get(id) != new.get(id))
+        .map(|&id| RowDiff {
+            id,
+            before: owned(old.get(&id)),
+            after: owned(new.get(&id)),
+        })
+        .collect();
+    diffs.sort_by_key(|diff| diff.id);
+    diffs
+}
+
+pub fn validate_date(date: &str) -> Result<(), ReporterError> {
+    // Dates are map keys and sort as strings, so only the canonical form is accepted
+    match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(parsed) if parsed.format("%Y-%m-%d").to_string() == date => Ok(()),
+        _ => Err(ReporterError::InvalidDate(date.to_string())),
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State {
+    pub fn load() -> Result<Self, ReporterError> {
+        let mut s = storage::open()?.load()?;
+        s.pending = Some(Vec::new());
+        Ok(s)
+    }
+
+    /// Loads without writing anything back, read only: for processes that
+    /// don't hold the instance lock.
+    pub fn peek() -> Result<Self, ReporterError> {
+        let s = storage::open()?.peek()?;
+        Ok(Self { read_only: true, ..s })
+    }
+
+    /// Loads and upgrades a state file in memory, leaving the file itself untouched.
+    pub fn load_from(path: &Path) -> Result<Self, ReporterError> {
+        let mut file = File::open(path)?;
+        let mut json_string = String::new();
+        file.read_to_string(&mut json_string)?;
+        let doc: serde_json::Value = serde_json::from_str(&json_string)?;
+        Self::from_document(doc).map(|(s, _)| s)
+    }
+
+    /// Builds a state from a serialized document of any known schema version.
+    /// Also returns the version the document had before the upgrade.
+    pub fn from_document(mut doc: serde_json::Value) -> Result<(Self, u32), ReporterError> {
+        let found = migrate(&mut doc)?;
+        let mut s: State = serde_json::from_value(doc)?;
+        s.cur_date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        Ok((s, found))
+    }
+
+    pub fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            max_id: 0,
+            rows: HashMap::new(),
+            cur_date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            read_only: false,
+            pending: None,
+        }
+    }
+
+    pub fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Self::new()
+        }
+    }
+
+    /// Copies an unreadable data file aside under a timestamped name, so that
+    /// nothing written later can destroy what is left of it.
+    pub fn quarantine() -> Result<PathBuf, ReporterError> {
+        let path = storage::open()?.path().to_path_buf();
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        let name = format!(
+            "gtk-reporter.corrupt-{}.{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            extension
+        );
+        let target = path.with_file_name(name);
+        std::fs::copy(&path, &target)?;
+        Ok(target)
+    }
+
+    pub fn save(&mut self) -> Result<(), ReporterError> {
+        if self.read_only {
+            return Err(ReporterError::ReadOnly);
+        }
+        self.save_with(storage::open()?.as_ref())
+    }
+
+    pub fn save_with(&mut self, storage: &dyn Storage) -> Result<(), ReporterError> {
+        storage.commit(self, self.pending.as_deref())?;
+        self.pending = Some(Vec::new());
+        Ok(())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), ReporterError> {
+        let json_string = serde_json::to_string(self)?;
+        write_atomic(path, |file| file.write_all(json_string.as_bytes()))
+    }
+
+    /// Whether both states hold the same tasks, regardless of the session fields.
+    pub fn same_data(&self, other: &State) -> bool {
+        self.max_id == other.max_id && self.rows == other.rows
+    }
+
+    /// Takes over the tasks of `other`, keeping this session's date and mode.
+    pub fn replace_data(&mut self, other: State) {
+        self.max_id = other.max_id;
+        self.rows = other.rows;
+        self.pending = None;
+    }
+
+    /// Declares the in-memory tasks identical to what storage holds.
+    pub fn mark_persisted(&mut self) {
+        self.pending = Some(Vec::new());
+    }
+
+    /// Runs `change` and saves the result. If either step fails the state is
+    /// left exactly as it was, so multi-step edits never apply halfway.
+    /// Saved changes are added to the journal; if only that fails, the change
+    /// stands and `ReporterError::Journal` says so.
+    pub fn transaction<T, F>(&mut self, change: F) -> Result<T, ReporterError>
+    where
+        F: FnOnce(&mut State) -> Result<T, ReporterError>,
+    {
+        self.transaction_diff(change).map(|(value, _)| value)
+    }
+
+    /// The same as `transaction`, also returning the tasks it changed.
+    pub fn transaction_diff<T, F>(&mut self, change: F) -> Result<(T, Vec<RowDiff>), ReporterError>
+    where
+        F: FnOnce(&mut State) -> Result<T, ReporterError>,
+    {
+        let (value, diffs) = self.save_change(change)?;
+        journal::record(&diffs)?;
+        Ok((value, diffs))
+    }
+
+    /// The same as `transaction_diff`, leaving the journal to the caller.
+    pub fn save_change<T, F>(&mut self, change: F) -> Result<(T, Vec<RowDiff>), ReporterError>
+    where
+        F: FnOnce(&mut State) -> Result<T, ReporterError>,
+    {
+        if self.read_only {
+            return Err(ReporterError::ReadOnly);
+        }
+        let mut draft = self.clone();
+        let value = change(&mut draft)?;
+        draft.save()?;
+        let diffs = diff_rows(self, &draft);
+        *self = draft;
+        Ok((value, diffs))
+    }
+
+    fn record(&mut self, mutation: Mutation) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(mutation);
+        }
+    }
+
+    pub fn delete_row(&mut self, key: String, id: u32) -> Result<(), ReporterError> {
+        let rows = self.rows.get_mut(&key).ok_or(ReporterError::DateNotFound)?;
+        let initial_len = rows.len();
+        rows.retain(|r| r.id != id);
+        if rows.len() == initial_len {
+            return Err(ReporterError::RowNotFound);
+        }
+        if rows.is_empty() {
+            self.rows.remove(&key);
+        }
+        self.record(Mutation::Delete { date: key, id });
+        Ok(())
+    }
+
+    pub fn move_row(&mut self, key: String, id: u32, new_key: String) -> Result<(), ReporterError> {
+        let source_rows = self.rows.get_mut(&key).ok_or(ReporterError::DateNotFound)?;
+        let row_index = source_rows
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or(ReporterError::RowNotFound)?;
+        let mut row = source_rows.remove(row_index);
+        row.updated_at = Some(Local::now());
+        if source_rows.is_empty() {
+            self.rows.remove(&key);
+        }
+        self.rows.entry(new_key.clone()).or_default().push(row);
+        self.record(Mutation::Move {
+            from: key,
+            to: new_key,
+            id,
+        });
+        Ok(())
+    }
+
+    /// Validates `change` and applies it in full, or leaves the state untouched
+    /// on error. Returns the task as it is afterwards; `cur_date` is not used.
+    pub fn apply(&mut self, change: RowChange) -> Result<Row, ReporterError> {
+        match change {
+            RowChange::Create { date, text, status, blocked } => {
+                let text = validate_text(&text)?;
+                validate_date(&date)?;
+                self.max_id += 1;
+                let now = Some(Local::now());
+                let row = Row {
+                    status,
+                    blocked,
+                    created_at: now,
+                    updated_at: now,
+                    status_changed_at: now,
+                    ..Row::new(self.max_id, text)
+                };
+                self.rows.entry(date.clone()).or_default().push(row.clone());
+                self.record(Mutation::Add { date, id: row.id });
+                Ok(row)
+            }
+            RowChange::Update { date, id, text, status, blocked, new_date } => {
+                let text = text.as_deref().map(validate_text).transpose()?;
+                if let Some(new_date) = &new_date {
+                    validate_date(new_date)?;
+                }
+                let rows = self.rows.get_mut(&date).ok_or(ReporterError::DateNotFound)?;
+                let index = rows
+                    .iter()
+                    .position(|r| r.id == id)
+                    .ok_or(ReporterError::RowNotFound)?;
+
+                let row = &mut rows[index];
+                let now = Some(Local::now());
+                let mut changed = false;
+                if let Some(text) = text.filter(|text| *text != row.text) {
+                    row.text = text;
+                    changed = true;
+                }
+                if let Some(status) = status.filter(|status| *status != row.status) {
+                    row.status = status;
+                    row.status_changed_at = now;
+                    changed = true;
+                }
+                if let Some(blocked) = blocked.filter(|blocked| *blocked != row.blocked) {
+                    row.blocked = blocked;
+                    changed = true;
+                }
+                if changed {
+                    row.updated_at = now;
+                    self.record(Mutation::Edit { date: date.clone(), id });
+                }
+                match new_date {
+                    Some(new_date) if new_date != date => {
+                        self.move_row(date, id, new_date.clone())?;
+                        self.get_row(&new_date, id).ok_or(ReporterError::RowNotFound)
+                    }
+                    _ => self.get_row(&date, id).ok_or(ReporterError::RowNotFound),
+                }
+            }
+        }
+    }
+
+    /// Moves or copies every unfinished task of `from` to `to` and returns them as
+    /// they are on `to`. Copies remember their original; a task copied before is skipped.
+    pub fn carry_over(&mut self, from: &str, to: &str, mode: CarryMode) -> Result<Vec<Row>, ReporterError> {
+        validate_date(to)?;
+        if from == to {
+            return Ok(Vec::new());
+        }
+        let already_copied: Vec<u32> = self
+            .get_rows_for_date(to)
+            .iter()
+            .filter_map(|row| row.carried_from)
+            .collect();
+        let unfinished: Vec<Row> = self
+            .get_rows_for_date(from)
+            .into_iter()
+            .filter(|row| !row.status.is_done() && !already_copied.contains(&row.id))
+            .collect();
+
+        let mut carried = Vec::new();
+        for row in unfinished {
+            match mode {
+                CarryMode::Move => {
+                    self.move_row(from.to_string(), row.id, to.to_string())?;
+                    carried.extend(self.get_row(to, row.id));
+                }
+                CarryMode::Copy => {
+                    self.max_id += 1;
+                    let now = Some(Local::now());
+                    let copy = Row {
+                        status: row.status,
+                        blocked: row.blocked,
+                        created_at: now,
+                        updated_at: now,
+                        status_changed_at: row.status_changed_at,
+                        carried_from: Some(row.id),
+                        ..Row::new(self.max_id, row.text)
+                    };
+                    self.rows.entry(to.to_string()).or_default().push(copy.clone());
+                    self.record(Mutation::Add {
+                        date: to.to_string(),
+                        id: copy.id,
+                    });
+                    carried.push(copy);
+                }
+            }
+        }
+        Ok(carried)
+    }
+
+    /// The latest day before `date` that has tasks.
+    pub fn previous_date(&self, date: &str) -> Option<String> {
+        self.rows.keys().filter(|d| d.as_str() < date).max().cloned()
+    }
+
+    /// Ids of task `id` and of the tasks it was carried over from, oldest first.
+    pub fn carry_chain(&self, id: u32) -> Vec<u32> {
+        let mut chain = vec![id];
+        let mut current = self.locate(id).and_then(|(_, row)| row.carried_from);
+        while let Some(origin) = current {
+            if chain.contains(&origin) {
+                break;
+            }
+            chain.insert(0, origin);
+            current = self.locate(origin).and_then(|(_, row)| row.carried_from);
+        }
+        chain
+    }
+
+    /// Finds task `id` on any date.
+    pub fn locate(&self, id: u32) -> Option<(String, Row)> {
+        self.rows.iter().find_map(|(date, rows)| {
+            rows.iter()
+                .find(|r| r.id == id)
+                .map(|row| (date.clone(), row.clone()))
+        })
+    }
+
+    /// Puts task `id` back the way `target` describes it, or removes it for `None`.
+    /// A task that has to be re-added goes to the end of its day.
+    pub fn restore_row(&mut self, id: u32, target: Option<(String, Row)>) -> Result<(), ReporterError> {
+        let current = self.locate(id).map(|(date, _)| date);
+        match (current, target) {
+            (None, None) => {}
+            (Some(date), None) => self.delete_row(date, id)?,
+            (None, Some((date, row))) => {
+                self.max_id = self.max_id.max(row.id);
+                self.rows.entry(date.clone()).or_default().push(row);
+                self.record(Mutation::Add { date, id });
+            }
+            (Some(from), Some((to, row))) => {
+                if from != to {
+                    self.move_row(from, id, to.clone())?;
+                }
+                // Replaced after moving, so the timestamps are the restored ones
+                let rows = self.rows.get_mut(&to).ok_or(ReporterError::DateNotFound)?;
+                if let Some(slot) = rows.iter_mut().find(|r| r.id == id) {
+                    *slot = row;
+                }
+                self.record(Mutation::Edit { date: to, id });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_rows_for_date(&self, date: &str) -> Vec<Row> {
+        self.rows.get(date).cloned().unwrap_or_default()
+    }
+
+    pub fn get_rows_sorted(&self, date: &str, order: TaskOrder) -> Vec<Row> {
+        let mut rows = self.get_rows_for_date(date);
+        match order {
+            TaskOrder::Manual => {}
+            TaskOrder::Created | TaskOrder::Updated => {
+                rows.sort_by_key(|row| std::cmp::Reverse(order.timestamp(row)));
+            }
+            TaskOrder::StatusChanged => rows.sort_by_key(|row| order.timestamp(row)),
+        }
+        rows
+    }
+
+    pub fn get_all_dates(&self) -> Vec<String> {
+        let mut dates: Vec<String> = self.rows.keys().cloned().collect();
+        dates.sort_by(|a, b| b.cmp(a));
+        dates
+    }
+
+    pub fn task_count(&self) -> usize {
+        self.rows.values().map(Vec::len).sum()
+    }
+
+    /// Earliest and latest dates that have tasks.
+    pub fn date_range(&self) -> Option<(String, String)> {
+        let first = self.rows.keys().min()?;
+        let last = self.rows.keys().max()?;
+        Some((first.clone(), last.clone()))
+    }
+
+    pub fn get_row(&self, date: &str, id: u32) -> Option<Row> {
+        self.rows
+            .get(date)
+            .and_then(|rows| rows.iter().find(|r| r.id == id))
+            .cloned()
+    }
+
+    /// The report for `date` in the standard layout.
+    pub fn generate_report(&self, date: &str) -> String {
+        Template::standard()
+            .render(&report::day_context(self, date))
+            .unwrap_or_else(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn add(state: &mut State, date: &str, text: &str) -> u32 {
+        status::init(Vec::new()).unwrap();
+        let change = RowChange::Create {
+            date: date.to_string(),
+            text: text.to_string(),
+            status: Status::new(status::WORKING),
+            blocked: false,
+        };
+        state.apply(change).unwrap().id
+    }
+
+    fn sample_state() -> State {
+        let mut state = State::new();
+        state.cur_date = "2026-02-19".to_string();
+        add(&mut state, "2026-02-19", "Первая задача");
+        add(&mut state, "2026-02-19", "Вторая задача");
+        state
+    }
+
+    #[test]
+    fn save_to_roundtrips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gtk-reporter.json");
+
+        sample_state().save_to(&path).unwrap();
+        let loaded = State::load_from(&path).unwrap();
+
+        assert_eq!(loaded.max_id, 2);
+        assert_eq!(loaded.get_rows_for_date("2026-02-19").len(), 2);
+    }
+
+    #[test]
+    fn failed_transaction_rolls_back() {
+        let mut state = sample_state();
+        let before = state.clone();
+
+        let result = state.transaction(|s| {
+            s.apply(RowChange::Update {
+                date: "2026-02-19".to_string(),
+                id: 1,
+                text: Some("Изменено".to_string()),
+                status: None,
+                blocked: None,
+                new_date: None,
+            })?;
+            s.move_row("2026-02-18".to_string(), 1, "2026-02-20".to_string())
+        });
+
+        assert!(matches!(result, Err(ReporterError::DateNotFound)));
+        assert!(state.same_data(&before));
+        assert_eq!(state.pending, before.pending);
+    }
+
+    #[test]
+    fn apply_creates_without_touching_cur_date() {
+        let mut state = sample_state();
+        let row = state
+            .apply(RowChange::Create {
+                date: "2026-02-21".to_string(),
+                text: "  Новая  ".to_string(),
+                status: Status::new(status::OPEN),
+                blocked: false,
+            })
+            .unwrap();
+
+        assert_eq!(state.cur_date, "2026-02-19");
+        assert_eq!(row.text, "Новая");
+        assert_eq!(state.get_row("2026-02-21", row.id), Some(row));
+    }
+
+    #[test]
+    fn apply_rejects_invalid_update_as_a_whole() {
+        let mut state = sample_state();
+        let before = state.clone();
+
+        let result = state.apply(RowChange::Update {
+            date: "2026-02-19".to_string(),
+            id: 1,
+            text: Some("Изменено".to_string()),
+            status: Some(Status::new(status::READY)),
+            blocked: None,
+            new_date: Some("2026-02-30".to_string()),
+        });
+
+        assert!(matches!(result, Err(ReporterError::InvalidDate(_))));
+        assert!(state.same_data(&before));
+    }
+
+    #[test]
+    fn restore_row_reverts_delete_and_move() {
+        let mut state = sample_state();
+        let deleted = state.locate(1);
+        let moved = state.locate(2);
+        state.delete_row("2026-02-19".to_string(), 1).unwrap();
+        state.move_row("2026-02-19".to_string(), 2, "2026-02-20".to_string()).unwrap();
+
+        state.restore_row(2, moved.clone()).unwrap();
+        state.restore_row(1, deleted.clone()).unwrap();
+
+        assert_eq!(state.locate(1), deleted);
+        assert_eq!(state.locate(2), moved);
+        assert!(state.get_rows_for_date("2026-02-20").is_empty());
+    }
+
+    #[test]
+    fn timestamps_follow_changes_and_old_rows_still_load() {
+        let mut state = sample_state();
+        let created = state.get_row("2026-02-19", 1).unwrap();
+        assert!(created.created_at.is_some());
+        assert_eq!(created.created_at, created.status_changed_at);
+
+        let unchanged = state
+            .apply(RowChange::Update {
+                date: "2026-02-19".to_string(),
+                id: 1,
+                text: Some(created.text.clone()),
+                status: Some(Status::new(status::TESTING)),
+                blocked: None,
+                new_date: None,
+            })
+            .unwrap();
+        assert_eq!(unchanged.created_at, created.created_at);
+        assert!(unchanged.status_changed_at >= created.status_changed_at);
+        assert_eq!(unchanged.updated_at, unchanged.status_changed_at);
+
+        let legacy: Row = serde_json::from_str(r#"{"id":7,"text":"Старая","status":"ready"}"#).unwrap();
+        assert_eq!(legacy.last_change(), None);
+        assert_eq!(serde_json::to_string(&legacy).unwrap(), r#"{"id":7,"text":"Старая","status":"ready"}"#);
+    }
+
+    #[test]
+    fn carry_over_copies_unfinished_once() {
+        let mut state = sample_state();
+        let ready = RowChange::Update {
+            date: "2026-02-19".to_string(),
+            id: 2,
+            text: None,
+            status: Some(Status::new(status::READY)),
+            blocked: None,
+            new_date: None,
+        };
+        state.apply(ready).unwrap();
+
+        let carried = state.carry_over("2026-02-19", "2026-02-20", CarryMode::Copy).unwrap();
+        assert_eq!(carried.len(), 1);
+        assert_eq!(carried[0].carried_from, Some(1));
+        assert_eq!(state.get_rows_for_date("2026-02-19").len(), 2);
+        assert_eq!(state.carry_chain(carried[0].id), vec![1, carried[0].id]);
+
+        let again = state.carry_over("2026-02-19", "2026-02-20", CarryMode::Copy).unwrap();
+        assert!(again.is_empty());
+
+        let moved = state.carry_over("2026-02-19", "2026-02-21", CarryMode::Move).unwrap();
+        assert_eq!(moved.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(state.locate(1).unwrap().0, "2026-02-21");
+    }
+
+    #[test]
+    fn failed_write_keeps_previous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gtk-reporter.json");
+        sample_state().save_to(&path).unwrap();
+        let before = std::fs::read(&path).unwrap();
+
+        let result = write_atomic(&path, |file| {
+            file.write_all(b"{\"max_id\": 3, \"rows\": {")?;
+            Err(std::io::Error::other("No space left on device"))
+        });
+
+        assert!(matches!(result, Err(ReporterError::IoError(_))));
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        assert!(State::load_from(&path).is_ok());
+        let leftovers = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(leftovers, 1, "temporary file must be cleaned up");
+    }
+
+    #[test]
+    fn unversioned_document_is_upgraded() {
+        let legacy = r#"{"max_id":1,"rows":{"2026-02-19":[{"id":1,"text":"Задача","status":"Testing"}]},"cur_date":"2026-02-19"}"#;
+
+        let (loaded, found) = State::from_document(serde_json::from_str(legacy).unwrap()).unwrap();
+
+        assert_eq!(found, 0);
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
+        assert_eq!(loaded.get_row("2026-02-19", 1).unwrap().status, Status::new(status::TESTING));
+    }
+
+    #[test]
+    fn v1_statuses_become_builtin_ids() {
+        let mut doc: serde_json::Value = serde_json::from_str(
+            r#"{"schema_version":1,"max_id":2,"rows":{"2026-02-19":[{"id":1,"text":"a","status":"Ready"},{"id":2,"text":"b","status":"Open"}]},"cur_date":""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(migrate(&mut doc).unwrap(), 1);
+
+        assert_eq!(doc["schema_version"], 2);
+        assert_eq!(doc["rows"]["2026-02-19"][0]["status"], status::READY);
+        assert_eq!(doc["rows"]["2026-02-19"][1]["status"], status::OPEN);
+    }
+
+    #[test]
+    fn future_schema_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gtk-reporter.json");
+        let doc = format!(r#"{{"schema_version":{},"max_id":0,"rows":{{}},"cur_date":""}}"#, SCHEMA_VERSION + 1);
+        std::fs::write(&path, &doc).unwrap();
+
+        let result = State::load_from(&path);
+
+        assert!(matches!(result, Err(ReporterError::UnsupportedSchema { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), doc);
+    }
+}
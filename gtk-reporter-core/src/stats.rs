@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::report::group_by_status;
+use crate::state::{Row, State};
+use crate::status::Status;
+
+/// How many days the daily charts cover.
+pub const DAYS: i64 = 30;
+/// How many weeks the weekly charts cover.
+pub const WEEKS: i64 = 12;
+
+/// Figures for the statistics screen, all counted by the day tasks are on.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// Tasks on each of the last `DAYS` days, oldest first.
+    pub per_day: Vec<(NaiveDate, usize)>,
+    /// Tasks per status for each of the last `WEEKS` weeks, by their Monday.
+    pub per_week: Vec<(NaiveDate, Vec<(Status, usize)>)>,
+    /// Average time from creation to a finished status, per week.
+    pub lead_time: Vec<(NaiveDate, Option<Duration>)>,
+    /// The same over all tasks.
+    pub lead_time_overall: Option<Duration>,
+    /// Finished tasks on each of the last `DAYS` days.
+    pub done_per_day: Vec<(NaiveDate, usize)>,
+    /// Working days in a row up to today with something finished.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+fn parse(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+fn monday(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday().into())
+}
+
+fn average(durations: &[Duration]) -> Option<Duration> {
+    let count = i32::try_from(durations.len()).ok().filter(|&count| count > 0)?;
+    Some(durations.iter().fold(Duration::zero(), |sum, d| sum + *d) / count)
+}
+
+/// Time from creation to the finished status it's in, when both are known.
+fn lead_time(row: &Row) -> Option<Duration> {
+    if !row.status.is_done() {
+        return None;
+    }
+    Some(row.status_changed_at? - row.created_at?)
+}
+
+pub fn collect(state: &State, today: NaiveDate, holidays: &[NaiveDate]) -> Stats {
+    let days: HashMap<NaiveDate, Vec<Row>> = state
+        .get_all_dates()
+        .iter()
+        .filter_map(|date| Some((parse(date)?, state.get_rows_for_date(date))))
+        .collect();
+    let done_on = |day: NaiveDate| days.get(&day).map_or(0, |rows| rows.iter().filter(|r| r.status.is_done()).count());
+
+    let window: Vec<NaiveDate> = (0..DAYS).rev().map(|back| today - Duration::days(back)).collect();
+    let per_day = window
+        .iter()
+        .map(|&day| (day, days.get(&day).map_or(0, Vec::len)))
+        .collect();
+    let done_per_day = window.iter().map(|&day| (day, done_on(day))).collect();
+
+    let this_week = monday(today);
+    let weeks: Vec<NaiveDate> = (0..WEEKS).rev().map(|back| this_week - Duration::weeks(back)).collect();
+    let week_rows = |week: NaiveDate| -> Vec<Row> {
+        (0..7)
+            .filter_map(|offset| days.get(&(week + Duration::days(offset))))
+            .flatten()
+            .cloned()
+            .collect()
+    };
+    let per_week = weeks
+        .iter()
+        .map(|&week| {
+            let rows = week_rows(week);
+            let counts = group_by_status(&rows)
+                .into_iter()
+                .map(|(status, rows)| (status, rows.len()))
+                .collect();
+            (week, counts)
+        })
+        .collect();
+    let lead_time_per_week = weeks
+        .iter()
+        .map(|&week| {
+            let durations: Vec<Duration> = week_rows(week).iter().filter_map(lead_time).collect();
+            (week, average(&durations))
+        })
+        .collect();
+    let all: Vec<Duration> = days.values().flatten().filter_map(lead_time).collect();
+
+    // Weekends and holidays with nothing finished neither count nor break a streak
+    let off = |day: NaiveDate| day.weekday().num_days_from_monday() >= 5 || holidays.contains(&day);
+    let (mut current, mut longest, mut run) = (None, 0, 0);
+    if let Some(first) = days.keys().min() {
+        let mut day = today;
+        // Today only counts once something is finished, it isn't over yet
+        if done_on(today) == 0 {
+            day -= Duration::days(1);
+        }
+        while day >= *first {
+            match done_on(day) {
+                0 if off(day) => {}
+                0 => {
+                    current.get_or_insert(run);
+                    longest = longest.max(run);
+                    run = 0;
+                }
+                _ => run += 1,
+            }
+            day -= Duration::days(1);
+        }
+    }
+    let current_streak = current.unwrap_or(run);
+    let longest_streak = longest.max(run);
+
+    Stats {
+        per_day,
+        per_week,
+        lead_time: lead_time_per_week,
+        lead_time_overall: average(&all),
+        done_per_day,
+        current_streak,
+        longest_streak,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RowChange;
+    use crate::state::tests::add;
+    use crate::status;
+
+    fn add_done(state: &mut State, date: &str) {
+        let id = add(state, date, "Задача");
+        state
+            .apply(RowChange::Update {
+                date: date.to_string(),
+                id,
+                text: None,
+                status: Some(Status::new(status::READY)),
+                blocked: None,
+                new_date: None,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn streaks_skip_weekends_and_count_finished_days() {
+        let mut state = State::new();
+        // Finished on Mon, then Thu, Fri and the next Mon; nothing on Tue and Wed
+        for date in ["2026-02-16", "2026-02-19", "2026-02-20", "2026-02-23"] {
+            add_done(&mut state, date);
+        }
+        add(&mut state, "2026-02-17", "Не закончена");
+
+        let today = NaiveDate::from_ymd_opt(2026, 2, 24).unwrap();
+        let stats = collect(&state, today, &[]);
+        assert_eq!(stats.current_streak, 3);
+        assert_eq!(stats.longest_streak, 3);
+        assert_eq!(stats.per_day.len(), DAYS as usize);
+        assert_eq!(stats.per_day.last(), Some(&(today, 0)));
+        assert!(stats.lead_time_overall.is_some());
+
+        let this_week = &stats.per_week.last().unwrap().1;
+        assert_eq!(this_week, &vec![(Status::new(status::READY), 1)]);
+    }
+}
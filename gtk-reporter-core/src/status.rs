@@ -0,0 +1,194 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ReporterError;
+
+pub const WORKING: &str = "working";
+pub const TESTING: &str = "testing";
+pub const READY: &str = "ready";
+pub const OPEN: &str = "open";
+
+/// A status as defined in the config file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusDef {
+    pub id: String,
+    pub label: String,
+    /// Position in lists and reports, lowest first.
+    #[serde(default)]
+    pub order: i32,
+    /// Any colour GTK understands, such as `#2ec27e`.
+    #[serde(default, alias = "color")]
+    pub colour: String,
+    /// Finished tasks are not carried over to the next day.
+    #[serde(default)]
+    pub done: bool,
+    /// Tasks in this status are listed under the stand-up blockers.
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+impl StatusDef {
+    fn new(id: &str, label: &str, order: i32, colour: &str, done: bool) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            order,
+            colour: colour.to_string(),
+            done,
+            blocked: false,
+        }
+    }
+}
+
+/// The statuses there were before they became configurable.
+pub fn builtin() -> Vec<StatusDef> {
+    vec![
+        StatusDef::new(WORKING, "В работе", 0, "#3584e4", false),
+        StatusDef::new(TESTING, "Передал в тестирование", 1, "#c64600", false),
+        StatusDef::new(READY, "Готово", 2, "#26a269", true),
+        StatusDef::new(OPEN, "Открыто", 3, "#77767b", false),
+    ]
+}
+
+static DEFINITIONS: OnceLock<Vec<StatusDef>> = OnceLock::new();
+
+/// Sets the statuses for this run, before any status is used; until then the
+/// built-in ones apply. An empty list keeps the built-in ones, and of several
+/// statuses with the same id only the first counts. Fails if different
+/// statuses are already in use.
+pub fn init(defs: Vec<StatusDef>) -> Result<(), ReporterError> {
+    let defs = prepare(defs);
+    if *DEFINITIONS.get_or_init(|| defs.clone()) == defs {
+        Ok(())
+    } else {
+        Err(ReporterError::StatusesInUse)
+    }
+}
+
+fn prepare(mut defs: Vec<StatusDef>) -> Vec<StatusDef> {
+    if defs.is_empty() {
+        defs = builtin();
+    }
+    let mut seen = Vec::new();
+    defs.retain(|def| {
+        let first = !seen.contains(&def.id);
+        seen.push(def.id.clone());
+        first
+    });
+    defs.sort_by_key(|def| def.order);
+    defs
+}
+
+fn definitions() -> &'static [StatusDef] {
+    DEFINITIONS.get_or_init(|| prepare(Vec::new()))
+}
+
+/// Maps the names the closed enum used to store onto the built-in ids.
+pub fn legacy_id(name: &str) -> Option<&'static str> {
+    match name {
+        "Working" => Some(WORKING),
+        "Testing" => Some(TESTING),
+        "Ready" => Some(READY),
+        "Open" => Some(OPEN),
+        _ => None,
+    }
+}
+
+/// The status of a task, stored as the id of its definition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Status(String);
+
+impl From<String> for Status {
+    // Old journal lines still carry enum names
+    fn from(id: String) -> Self {
+        match legacy_id(&id) {
+            Some(builtin) => Self(builtin.to_string()),
+            None => Self(id),
+        }
+    }
+}
+
+impl From<Status> for String {
+    fn from(status: Status) -> Self {
+        status.0
+    }
+}
+
+impl Status {
+    pub fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
+
+    /// What new tasks start with: the first configured status.
+    pub fn initial() -> Self {
+        Self(definitions()[0].id.clone())
+    }
+
+    /// All configured statuses in their order.
+    pub fn all() -> Vec<Status> {
+        definitions().iter().map(|def| Self(def.id.clone())).collect()
+    }
+
+    /// A configured status by id or label, ignoring case.
+    pub fn find(name: &str) -> Option<Status> {
+        let name = name.trim().to_lowercase();
+        definitions()
+            .iter()
+            .find(|def| def.id.to_lowercase() == name || def.label.to_lowercase() == name)
+            .map(|def| Self(def.id.clone()))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+
+    /// `None` for ids that are no longer in the config.
+    pub fn def(&self) -> Option<&'static StatusDef> {
+        definitions().iter().find(|def| def.id == self.0)
+    }
+
+    /// Unknown ids are shown as they are.
+    pub fn label(&self) -> &str {
+        self.def().map_or(&self.0, |def| &def.label)
+    }
+
+    pub fn colour(&self) -> &str {
+        self.def().map_or("", |def| &def.colour)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.def().is_some_and(|def| def.done)
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.def().is_some_and(|def| def.blocked)
+    }
+
+    /// Sort key that puts unknown ids after every configured status.
+    pub fn order(&self) -> (i32, &str) {
+        (self.def().map_or(i32::MAX, |def| def.order), &self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_are_found_by_id_or_label_and_unknown_ones_sort_last() {
+        init(Vec::new()).unwrap();
+        assert_eq!(Status::find("READY"), Some(Status::new(READY)));
+        assert_eq!(Status::find(" в работе "), Some(Status::new(WORKING)));
+        assert_eq!(Status::find("nope"), None);
+
+        let legacy: Status = serde_json::from_str("\"Testing\"").unwrap();
+        assert_eq!(legacy, Status::new(TESTING));
+
+        let unknown = Status::new("archived");
+        assert_eq!(unknown.label(), "archived");
+        assert!(!unknown.is_done());
+        assert!(Status::all().iter().all(|status| status.order() < unknown.order()));
+    }
+}
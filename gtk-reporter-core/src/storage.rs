@@ -0,0 +1,238 @@
+use std::path::{Path, PathBuf};
+
+use crate::backup;
+use crate::error::ReporterError;
+use crate::paths;
+use crate::sqlite::SqliteStorage;
+use crate::state::{Mutation, SCHEMA_VERSION, State};
+
+/// Where tasks are persisted. Row operations receive the state as it is after
+/// the change, so backends that can't write a single row may rewrite it all.
+pub trait Storage {
+    fn path(&self) -> &Path;
+
+    /// Loads and upgrades what is stored; an older schema is written back upgraded.
+    fn load(&self) -> Result<State, ReporterError>;
+
+    /// Loads and upgrades in memory only, for processes that may not write.
+    fn peek(&self) -> Result<State, ReporterError>;
+
+    /// Replaces everything stored with `state`.
+    fn save(&self, state: &State) -> Result<(), ReporterError>;
+
+    fn add(&self, state: &State, date: &str, id: u32) -> Result<(), ReporterError>;
+
+    fn edit(&self, state: &State, date: &str, id: u32) -> Result<(), ReporterError>;
+
+    fn move_row(&self, state: &State, from: &str, to: &str, id: u32) -> Result<(), ReporterError>;
+
+    fn delete(&self, state: &State, date: &str, id: u32) -> Result<(), ReporterError>;
+
+    /// Persists `mutations` that led to `state`; `None` asks for a full rewrite.
+    fn commit(&self, state: &State, mutations: Option<&[Mutation]>) -> Result<(), ReporterError> {
+        let Some(mutations) = mutations else {
+            return self.save(state);
+        };
+        for mutation in mutations {
+            match mutation {
+                Mutation::Add { date, id } => self.add(state, date, *id)?,
+                Mutation::Edit { date, id } => self.edit(state, date, *id)?,
+                Mutation::Move { from, to, id } => self.move_row(state, from, to, *id)?,
+                Mutation::Delete { date, id } => self.delete(state, date, *id)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    Json,
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "sqlite" => Some(Self::Sqlite),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// The database is used once it exists in the data directory, the JSON file otherwise.
+pub fn active_kind() -> Result<StorageKind, ReporterError> {
+    if paths::database_file()?.exists() {
+        Ok(StorageKind::Sqlite)
+    } else {
+        Ok(StorageKind::Json)
+    }
+}
+
+pub fn open() -> Result<Box<dyn Storage>, ReporterError> {
+    open_kind(active_kind()?)
+}
+
+fn open_kind(kind: StorageKind) -> Result<Box<dyn Storage>, ReporterError> {
+    match kind {
+        StorageKind::Json => Ok(Box::new(JsonStorage::new(paths::state_file()?))),
+        StorageKind::Sqlite => Ok(Box::new(SqliteStorage::open(&paths::database_file()?)?)),
+    }
+}
+
+/// Copies all tasks into the `to` format and moves the old file aside, so the
+/// new one is picked up from now on. Returns where the old file went.
+pub fn convert(to: StorageKind) -> Result<PathBuf, ReporterError> {
+    let from_kind = active_kind()?;
+    if from_kind == to {
+        return Err(ReporterError::AlreadyInFormat(to.to_str()));
+    }
+    let target = match to {
+        StorageKind::Json => paths::state_file()?,
+        StorageKind::Sqlite => paths::database_file()?,
+    };
+    convert_into(open_kind(from_kind)?.as_ref(), to, &target)
+}
+
+/// Writes everything `from` holds into a new `to` file at `target`. The file
+/// is made under another name first: a half-written one in its place would be
+/// picked up on the next start instead of the data.
+fn convert_into(from: &dyn Storage, to: StorageKind, target: &Path) -> Result<PathBuf, ReporterError> {
+    let state = from.load()?;
+    let partial = target.with_file_name(format!(
+        "{}.partial",
+        target.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let written = match to {
+        StorageKind::Json => JsonStorage::new(partial.clone()).save(&state),
+        // Closed again before the rename
+        StorageKind::Sqlite => SqliteStorage::open(&partial).and_then(|storage| storage.save(&state)),
+    };
+    if let Err(err) = written.and_then(|()| Ok(std::fs::rename(&partial, target)?)) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(err);
+    }
+
+    let name = from.path().file_name().unwrap_or_default().to_string_lossy();
+    let aside = from.path().with_file_name(format!(
+        "{}.converted-{}",
+        name,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::rename(from.path(), &aside)?;
+    Ok(aside)
+}
+
+/// The original single-file format: every change rewrites the whole document.
+#[derive(Debug)]
+pub struct JsonStorage {
+    path: PathBuf,
+}
+
+impl JsonStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&self) -> Result<State, ReporterError> {
+        let contents = std::fs::read_to_string(&self.path)?;
+        let (s, found) = State::from_document(serde_json::from_str(&contents)?)?;
+        if found < SCHEMA_VERSION {
+            backup::snapshot_before_migration(&self.path, found, contents.as_bytes())?;
+            s.save_to(&self.path)?;
+        }
+        Ok(s)
+    }
+
+    fn peek(&self) -> Result<State, ReporterError> {
+        State::load_from(&self.path)
+    }
+
+    fn save(&self, state: &State) -> Result<(), ReporterError> {
+        backup::snapshot_before_save(&self.path)?;
+        state.save_to(&self.path)
+    }
+
+    fn add(&self, state: &State, _date: &str, _id: u32) -> Result<(), ReporterError> {
+        self.save(state)
+    }
+
+    fn edit(&self, state: &State, _date: &str, _id: u32) -> Result<(), ReporterError> {
+        self.save(state)
+    }
+
+    fn move_row(&self, state: &State, _from: &str, _to: &str, _id: u32) -> Result<(), ReporterError> {
+        self.save(state)
+    }
+
+    fn delete(&self, state: &State, _date: &str, _id: u32) -> Result<(), ReporterError> {
+        self.save(state)
+    }
+
+    fn commit(&self, state: &State, _mutations: Option<&[Mutation]>) -> Result<(), ReporterError> {
+        self.save(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::add;
+
+    #[test]
+    fn json_load_upgrades_file_and_keeps_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gtk-reporter.json");
+        let legacy = r#"{"max_id":1,"rows":{"2026-02-19":[{"id":1,"text":"Задача","status":"Working"}]},"cur_date":"2026-02-19"}"#;
+        std::fs::write(&path, legacy).unwrap();
+
+        let storage = JsonStorage::new(path.clone());
+        assert_eq!(storage.peek().unwrap().rows["2026-02-19"][0].text, "Задача");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), legacy);
+        assert!(backup::list_snapshots(&path).unwrap().is_empty());
+        storage.load().unwrap();
+
+        let upgraded: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(upgraded["schema_version"], SCHEMA_VERSION);
+        let snapshots = backup::list_snapshots(&path).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(std::fs::read_to_string(&snapshots[0].path).unwrap(), legacy);
+    }
+
+    #[test]
+    fn failed_conversion_leaves_no_database_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("gtk-reporter.json");
+        let database = dir.path().join("gtk-reporter.db");
+        let mut state = State::new();
+        add(&mut state, "2026-02-19", "Задача");
+        state.save_to(&json).unwrap();
+
+        // The database's first save can't write its daily backup
+        let backups = backup::backup_dir(&database);
+        std::fs::write(&backups, "").unwrap();
+        let from = JsonStorage::new(json.clone());
+        assert!(convert_into(&from, StorageKind::Sqlite, &database).is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+        assert!(json.exists());
+
+        std::fs::remove_file(&backups).unwrap();
+        let aside = convert_into(&from, StorageKind::Sqlite, &database).unwrap();
+        assert!(!json.exists() && aside.exists());
+        assert!(SqliteStorage::open(&database).unwrap().load().unwrap().same_data(&state));
+    }
+}
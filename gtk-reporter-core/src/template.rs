@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::error::ReporterError;
+use crate::paths;
+use crate::state::write_atomic;
+
+// A small subset of Mustache: {{name}} inserts a value, {{#name}}...{{/name}}
+// repeats for every item of a list or shows for a true flag or non-empty text,
+// {{^name}}...{{/name}} shows when it doesn't, and {{! ... }} is a comment.
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Var(String),
+    Section {
+        name: String,
+        inverted: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// What a template can refer to by name.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Text(String),
+    Flag(bool),
+    List(Vec<Context>),
+}
+
+pub type Context = HashMap<&'static str, Value>;
+
+/// The same data for programs rather than templates.
+pub fn to_json(ctx: &Context) -> serde_json::Value {
+    let value = |value: &Value| match value {
+        Value::Text(text) => serde_json::Value::from(text.as_str()),
+        Value::Flag(flag) => serde_json::Value::from(*flag),
+        Value::List(items) => serde_json::Value::Array(items.iter().map(to_json).collect()),
+    };
+    ctx.iter().map(|(key, v)| (key.to_string(), value(v))).collect()
+}
+
+fn parse(source: &str) -> Result<Vec<Node>, String> {
+    // Sections being parsed, innermost last, each with what it holds so far
+    let mut open: Vec<(String, bool, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("тег не закрыт: «{{{{{}»", after.lines().next().unwrap_or("")))?;
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+
+        if let Some(name) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+            let inverted = tag.starts_with('^');
+            open.push((name.trim().to_string(), inverted, std::mem::take(&mut nodes)));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            let Some((open_name, inverted, outer)) = open.pop() else {
+                return Err(format!("«{{{{/{}}}}}» без открывающего тега", name));
+            };
+            if open_name != name {
+                return Err(format!("ожидался «{{{{/{}}}}}», а не «{{{{/{}}}}}»", open_name, name));
+            }
+            let body = std::mem::replace(&mut nodes, outer);
+            nodes.push(Node::Section {
+                name: open_name,
+                inverted,
+                body,
+            });
+        } else if !tag.starts_with('!') {
+            nodes.push(Node::Var(tag.to_string()));
+        }
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    if let Some((name, _, _)) = open.pop() {
+        return Err(format!("раздел «{{{{#{}}}}}» не закрыт", name));
+    }
+    Ok(nodes)
+}
+
+fn lookup<'a>(stack: &[&'a Context], name: &str) -> Option<&'a Value> {
+    stack.iter().rev().find_map(|ctx| ctx.get(name))
+}
+
+fn render_nodes(nodes: &[Node], stack: &mut Vec<&Context>, escape: fn(&str) -> String, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => match lookup(stack, name) {
+                Some(Value::Text(text)) => out.push_str(&escape(text)),
+                Some(Value::List(items)) => out.push_str(&items.len().to_string()),
+                Some(Value::Flag(_)) | None => {}
+            },
+            Node::Section { name, inverted, body } => match lookup(stack, name) {
+                Some(Value::List(items)) if !inverted => {
+                    for item in items {
+                        stack.push(item);
+                        render_nodes(body, stack, escape, out);
+                        stack.pop();
+                    }
+                }
+                value => {
+                    let shown = match value {
+                        Some(Value::List(items)) => !items.is_empty(),
+                        Some(Value::Flag(flag)) => *flag,
+                        Some(Value::Text(text)) => !text.is_empty(),
+                        None => false,
+                    };
+                    if shown != *inverted {
+                        render_nodes(body, stack, escape, out);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// A report layout, either shipped with the app or from the templates directory.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub source: String,
+    pub builtin: bool,
+    nodes: Result<Vec<Node>, String>,
+}
+
+impl Template {
+    pub fn new(name: &str, source: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source: source.to_string(),
+            builtin: false,
+            nodes: parse(source),
+        }
+    }
+
+    /// Fails if the template has a syntax error.
+    pub fn render(&self, ctx: &Context) -> Result<String, ReporterError> {
+        self.render_escaped(ctx, str::to_string)
+    }
+
+    /// Like `render`, passing every inserted value through `escape`, for
+    /// output formats with markup of their own.
+    pub fn render_escaped(&self, ctx: &Context, escape: fn(&str) -> String) -> Result<String, ReporterError> {
+        let nodes = self.nodes.as_ref().map_err(|err| ReporterError::Template(err.clone()))?;
+        let mut out = String::new();
+        render_nodes(nodes, &mut vec![ctx], escape, &mut out);
+        Ok(out)
+    }
+
+    /// The layout reports always had.
+    pub fn standard() -> Self {
+        builtin().swap_remove(0)
+    }
+
+    /// Whether this is the shipped standard layout, which the Markdown and
+    /// HTML versions of a report follow.
+    pub fn is_standard(&self) -> bool {
+        self.builtin && self.name == STANDARD
+    }
+}
+
+pub const STANDARD: &str = "Стандартный";
+
+const BUILTIN: [(&str, &str); 3] = [
+    (
+        STANDARD,
+        "Отчет {{date}}\n\n\
+         {{#statuses}}=== {{label}}{{#from}} ({{count}}){{/from}} ===\n\
+         {{#days}}{{date}}:\n{{#rows}}• {{text}}\n{{/rows}}{{/days}}\
+         {{^days}}{{#rows}}• {{text}}{{#times}} (×{{times}}){{/times}}\n{{/rows}}{{/days}}\n{{/statuses}}\
+         {{^statuses}}{{#from}}Нет задач за этот период.{{/from}}{{^from}}Нет задач за эту дату.{{/from}}{{/statuses}}",
+    ),
+    (
+        "Кратко",
+        "{{date}}: {{#statuses}}{{label}} — {{#rows}}{{text}}{{^last}}, {{/last}}{{/rows}}{{^last}}; {{/last}}{{/statuses}}\
+         {{^statuses}}задач нет{{/statuses}}",
+    ),
+    (
+        "Нумерованный",
+        "Отчет за {{date}} (задач: {{count}})\n\n\
+         {{#statuses}}{{label}} ({{count}}):\n{{#rows}}{{number}}. {{text}}\n{{/rows}}\n{{/statuses}}",
+    ),
+];
+
+pub fn builtin() -> Vec<Template> {
+    BUILTIN
+        .iter()
+        .map(|(name, source)| Template {
+            builtin: true,
+            ..Template::new(name, source)
+        })
+        .collect()
+}
+
+pub fn templates_dir() -> Result<PathBuf, ReporterError> {
+    Ok(paths::data_dir()?.join("templates"))
+}
+
+/// Built-in templates followed by the `*.txt` files of the templates directory.
+pub fn load_all() -> Result<Vec<Template>, ReporterError> {
+    let mut templates = builtin();
+    let dir = templates_dir()?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(templates),
+        Err(err) => return Err(err.into()),
+    };
+    let mut user = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "txt")
+            && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+        {
+            user.push(Template::new(name, &std::fs::read_to_string(&path)?));
+        }
+    }
+    user.sort_by(|a, b| a.name.cmp(&b.name));
+    templates.extend(user);
+    Ok(templates)
+}
+
+/// Writes a user template as `<name>.txt`, replacing one with the same name.
+pub fn save(name: &str, source: &str) -> Result<PathBuf, ReporterError> {
+    let name = name.trim();
+    let reserved = BUILTIN.iter().any(|(builtin, _)| *builtin == name);
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) || reserved {
+        return Err(ReporterError::TemplateName(name.to_string()));
+    }
+    let path = templates_dir()?.join(format!("{}.txt", name));
+    write_atomic(&path, |file| file.write_all(source.as_bytes()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+
+    #[test]
+    fn sections_loop_and_invert() {
+        let rows = ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| Context::from([("text", text(t)), ("last", Value::Flag(i == 2))]))
+            .collect();
+        let ctx = Context::from([("date", text("2026-02-19")), ("rows", Value::List(rows))]);
+
+        let template = Template::new("t", "{{date}}: {{#rows}}{{text}}{{^last}}, {{/last}}{{/rows}}{{! comment }}");
+        assert_eq!(template.render(&ctx).unwrap(), "2026-02-19: a, b, c");
+
+        let empty = Context::from([("rows", Value::List(Vec::new()))]);
+        let template = Template::new("t", "{{#rows}}x{{/rows}}{{^rows}}пусто{{/rows}}");
+        assert_eq!(template.render(&empty).unwrap(), "пусто");
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        for source in ["{{#rows}}", "{{/rows}}", "{{#a}}{{/b}}", "{{date"] {
+            let result = Template::new("t", source).render(&Context::new());
+            assert!(matches!(result, Err(ReporterError::Template(_))), "{}", source);
+        }
+        for template in builtin() {
+            assert!(template.render(&Context::new()).is_ok(), "{}", template.name);
+        }
+    }
+
+    #[test]
+    fn only_the_shipped_standard_layout_counts_as_standard() {
+        assert!(Template::standard().is_standard());
+        assert!(!Template::new(STANDARD, "{{date}}").is_standard());
+        assert_eq!(builtin().iter().filter(|t| t.is_standard()).count(), 1);
+    }
+}
@@ -0,0 +1,512 @@
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::cell::RefCell;
+use std::io::Read;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use gtk_reporter_core::config::ApiConfig;
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::history::{Command, History};
+use gtk_reporter_core::report::{self, RangeGrouping};
+use gtk_reporter_core::state::{RowChange, State, validate_date};
+use gtk_reporter_core::status::Status;
+use gtk_reporter_core::template::{self, Template};
+
+use crate::cli::TaskJson;
+
+// A task or two; anything bigger is a mistake
+const MAX_BODY: u64 = 64 * 1024;
+// How long a request waits for the window before giving up
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An HTTP response, built on the main thread and sent by the server thread.
+#[derive(Debug)]
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Reply {
+    fn json(status: u16, value: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: value.to_string(),
+        }
+    }
+
+    fn text(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, json!({ "error": message }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewTask {
+    text: String,
+    date: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    blocked: bool,
+}
+
+/// Fields left out keep their current value.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskPatch {
+    text: Option<String>,
+    status: Option<String>,
+    blocked: Option<bool>,
+    date: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Text,
+    Markdown,
+    Json,
+}
+
+/// A request that got past the token check and the router.
+#[derive(Debug)]
+enum Request {
+    Dates,
+    Tasks {
+        date: String,
+    },
+    Create(NewTask),
+    Update {
+        id: u32,
+        patch: TaskPatch,
+    },
+    Delete {
+        id: u32,
+    },
+    Report {
+        date: Option<String>,
+        range: Option<(String, String)>,
+        merged: bool,
+        format: ReportFormat,
+    },
+}
+
+/// A request and where its reply goes.
+struct Job {
+    request: Request,
+    /// Set by whoever gets to it first: the main loop to run the request, or
+    /// the server thread to give up on it. So a request is either run and
+    /// answered or, after a timeout, never run at all.
+    claimed: Arc<AtomicBool>,
+    reply: mpsc::Sender<Reply>,
+}
+
+fn status_code(err: &ReporterError) -> u16 {
+    match err {
+        ReporterError::DateNotFound | ReporterError::RowNotFound => 404,
+        ReporterError::EmptyText
+        | ReporterError::InvalidDate(_)
+        | ReporterError::InvalidRange { .. }
+        | ReporterError::UnknownStatus(_)
+        | ReporterError::Template(_) => 400,
+        ReporterError::ReadOnly => 409,
+        _ => 500,
+    }
+}
+
+fn parse_id(value: &str) -> Result<u32, Reply> {
+    value
+        .parse()
+        .map_err(|_| Reply::error(400, &format!("Неверный номер задачи: {}", value)))
+}
+
+fn parse_body<T: DeserializeOwned>(body: &str) -> Result<T, Reply> {
+    serde_json::from_str(body).map_err(|err| Reply::error(400, &format!("Неверный JSON: {}", err)))
+}
+
+fn parse_report(query: &str) -> Result<Request, Reply> {
+    let (mut date, mut from, mut to, mut merged, mut format) = (None, None, None, false, ReportFormat::Text);
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "date" => date = Some(value.to_string()),
+            "from" => from = Some(value.to_string()),
+            "to" => to = Some(value.to_string()),
+            "merged" => merged = value != "false",
+            "format" => {
+                format = match value {
+                    "text" => ReportFormat::Text,
+                    "markdown" => ReportFormat::Markdown,
+                    "json" => ReportFormat::Json,
+                    _ => return Err(Reply::error(400, &format!("Неизвестный формат отчёта: {}", value))),
+                }
+            }
+            _ => return Err(Reply::error(400, &format!("Неизвестный параметр: {}", key))),
+        }
+    }
+    let range = match (from, to) {
+        (Some(from), Some(to)) => Some((from, to)),
+        (None, None) => None,
+        _ => return Err(Reply::error(400, "from и to указываются вместе")),
+    };
+    if date.is_some() && range.is_some() {
+        return Err(Reply::error(400, "Укажите либо date, либо from и to"));
+    }
+    Ok(Request::Report {
+        date,
+        range,
+        merged,
+        format,
+    })
+}
+
+fn route(method: &str, url: &str, body: &str) -> Result<Request, Reply> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["dates"]) => Ok(Request::Dates),
+        ("GET", ["dates", date, "tasks"]) => Ok(Request::Tasks { date: date.to_string() }),
+        ("POST", ["tasks"]) => Ok(Request::Create(parse_body(body)?)),
+        ("PATCH", ["tasks", id]) => Ok(Request::Update {
+            id: parse_id(id)?,
+            patch: parse_body(body)?,
+        }),
+        ("DELETE", ["tasks", id]) => Ok(Request::Delete { id: parse_id(id)? }),
+        ("GET", ["report"]) => parse_report(query),
+        (_, ["dates"] | ["dates", _, "tasks"] | ["tasks"] | ["tasks", _] | ["report"]) => {
+            Err(Reply::error(405, &format!("{} здесь не поддерживается", method)))
+        }
+        _ => Err(Reply::error(404, "Нет такого адреса")),
+    }
+}
+
+fn find_status(name: &str) -> Result<Status, ReporterError> {
+    Status::find(name).ok_or_else(|| ReporterError::UnknownStatus(name.to_string()))
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Task `id` as it is now.
+fn task(state: &State, id: u32) -> Result<serde_json::Value, ReporterError> {
+    let (date, row) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+    Ok(serde_json::to_value(TaskJson::new(&date, &row))?)
+}
+
+/// Runs one request. Returns the reply and whether the tasks changed.
+fn handle(state: &mut State, history: &mut History, request: Request) -> Result<(Reply, bool), ReporterError> {
+    match request {
+        Request::Dates => Ok((Reply::json(200, json!(state.get_all_dates())), false)),
+        Request::Tasks { date } => {
+            validate_date(&date)?;
+            let rows = state.get_rows_for_date(&date);
+            let tasks: Vec<TaskJson> = rows.iter().map(|row| TaskJson::new(&date, row)).collect();
+            Ok((Reply::json(200, serde_json::to_value(tasks)?), false))
+        }
+        Request::Create(new) => {
+            let status = new.status.as_deref().map(find_status).transpose()?.unwrap_or_else(Status::initial);
+            let change = RowChange::Create {
+                date: new.date.unwrap_or_else(today),
+                text: new.text,
+                status,
+                blocked: new.blocked,
+            };
+            let rows = history.execute(state, Command::Apply(change))?;
+            let id = rows.first().ok_or(ReporterError::RowNotFound)?.id;
+            Ok((Reply::json(201, task(state, id)?), true))
+        }
+        Request::Update { id, patch } => {
+            let (date, _) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            let change = RowChange::Update {
+                date,
+                id,
+                text: patch.text,
+                status: patch.status.as_deref().map(find_status).transpose()?,
+                blocked: patch.blocked,
+                new_date: patch.date,
+            };
+            history.execute(state, Command::Apply(change))?;
+            Ok((Reply::json(200, task(state, id)?), true))
+        }
+        Request::Delete { id } => {
+            let (date, _) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            history.execute(state, Command::Delete { date: date.clone(), id })?;
+            Ok((Reply::json(200, json!({ "deleted": id, "date": date })), true))
+        }
+        Request::Report {
+            date,
+            range,
+            merged,
+            format,
+        } => {
+            let ctx = match range {
+                Some((from, to)) => {
+                    let grouping = if merged { RangeGrouping::Merged } else { RangeGrouping::ByDay };
+                    report::range_context(state, &from, &to, grouping)?
+                }
+                None => {
+                    let date = date.unwrap_or_else(today);
+                    validate_date(&date)?;
+                    report::day_context(state, &date)
+                }
+            };
+            let reply = match format {
+                ReportFormat::Text => Reply::text("text/plain; charset=utf-8", Template::standard().render(&ctx)?),
+                ReportFormat::Markdown => Reply::text("text/markdown; charset=utf-8", report::markdown(&ctx)),
+                ReportFormat::Json => Reply::json(200, template::to_json(&ctx)),
+            };
+            Ok((reply, false))
+        }
+    }
+}
+
+/// Runs a request on the main thread, where the tasks live.
+fn run(state: &RefCell<State>, history: &RefCell<History>, on_change: &dyn Fn(), request: Request) -> Reply {
+    // Only a nested main loop, such as a dialog's, could find them borrowed
+    let (Ok(mut s), Ok(mut h)) = (state.try_borrow_mut(), history.try_borrow_mut()) else {
+        return Reply::error(503, "Окно сейчас изменяет задачи, повторите позже");
+    };
+    let result = handle(&mut s, &mut h, request);
+    drop((s, h));
+    match result {
+        Ok((reply, changed)) => {
+            if changed {
+                on_change();
+            }
+            reply
+        }
+        Err(err) => Reply::error(status_code(&err), &err.to_string()),
+    }
+}
+
+/// Compares without stopping at the first difference, so the time taken
+/// tells nothing about the token.
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn authorized(request: &tiny_http::Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Authorization") && same(header.value.as_str(), &expected))
+}
+
+/// Answers one request on the server thread, handing it to the main thread.
+fn respond(request: &mut tiny_http::Request, token: &str, jobs: &async_channel::Sender<Job>) -> Reply {
+    if !authorized(request, token) {
+        return Reply::error(401, "Неверный токен");
+    }
+    let too_large = || Reply::error(413, &format!("Тело запроса больше {} байт", MAX_BODY));
+    if request.body_length().is_some_and(|length| length as u64 > MAX_BODY) {
+        return too_large();
+    }
+    // Chunked bodies don't say their length up front
+    let mut body = String::new();
+    if request.as_reader().take(MAX_BODY + 1).read_to_string(&mut body).is_err() {
+        return Reply::error(400, "Тело запроса не в UTF-8");
+    }
+    if body.len() as u64 > MAX_BODY {
+        return too_large();
+    }
+    let routed = match route(request.method().as_str(), request.url(), &body) {
+        Ok(routed) => routed,
+        Err(reply) => return reply,
+    };
+    let (reply, answer) = mpsc::channel();
+    let claimed = Arc::new(AtomicBool::new(false));
+    let job = Job {
+        request: routed,
+        claimed: claimed.clone(),
+        reply,
+    };
+    if jobs.send_blocking(job).is_err() {
+        return Reply::error(503, "Окно закрывается");
+    }
+    let closing = || Reply::error(503, "Окно закрывается");
+    match answer.recv_timeout(REPLY_TIMEOUT) {
+        Ok(reply) => reply,
+        Err(mpsc::RecvTimeoutError::Timeout) if !claimed.swap(true, Ordering::SeqCst) => {
+            Reply::error(503, "Окно не ответило, запрос не выполнен, повторите позже")
+        }
+        // Already running, and quick once it does
+        Err(mpsc::RecvTimeoutError::Timeout) => answer.recv().unwrap_or_else(|_| closing()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => closing(),
+    }
+}
+
+fn serve(server: &tiny_http::Server, token: &str, jobs: &async_channel::Sender<Job>) {
+    for mut request in server.incoming_requests() {
+        let reply = respond(&mut request, token, jobs);
+        let content_type =
+            tiny_http::Header::from_bytes("Content-Type", reply.content_type).expect("content types are valid headers");
+        let response = tiny_http::Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type);
+        if let Err(err) = request.respond(response) {
+            eprintln!("Failed to answer an HTTP API request: {}", err);
+        }
+    }
+}
+
+/// The HTTP API on localhost. Requests are read on a thread of their own and
+/// run on the main loop, so they never race the window over the tasks.
+/// Dropping it stops the server.
+pub struct Api {
+    server: Arc<tiny_http::Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Api {
+    /// Serves `state` on the configured port, changing it through `history` so
+    /// changes can be undone. `on_change` is called after a request changed the tasks.
+    pub fn start<F: Fn() + 'static>(
+        config: &ApiConfig,
+        state: Rc<RefCell<State>>,
+        history: Rc<RefCell<History>>,
+        on_change: F,
+    ) -> Result<Self, ReporterError> {
+        if config.token.is_empty() {
+            return Err(ReporterError::NoApiToken);
+        }
+        let server = tiny_http::Server::http(("127.0.0.1", config.port)).map_err(std::io::Error::other)?;
+        let server = Arc::new(server);
+
+        let (jobs, queue) = async_channel::unbounded::<Job>();
+        glib::spawn_future_local(async move {
+            while let Ok(job) = queue.recv().await {
+                if job.claimed.swap(true, Ordering::SeqCst) {
+                    continue;
+                }
+                let reply = run(&state, &history, &on_change, job.request);
+                // The server thread may have stopped waiting
+                let _ = job.reply.send(reply);
+            }
+        });
+        let thread = thread::spawn({
+            let server = server.clone();
+            let token = config.token.clone();
+            move || serve(&server, &token, &jobs)
+        });
+        Ok(Self {
+            server,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for Api {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::io::Write;
+    use std::net::{SocketAddr, TcpStream};
+
+    use crate::test_support;
+
+    /// A bare HTTP/1.1 exchange, so the test needs no client library.
+    fn send(address: SocketAddr, method: &str, path: &str, token: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            token,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn requests_need_the_token_and_work_on_the_shared_state() {
+        let _data_dir = test_support::data_dir();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let state = Rc::new(RefCell::new(State::new()));
+                let changes = Rc::new(Cell::new(0));
+                let on_change = {
+                    let changes = changes.clone();
+                    move || changes.set(changes.get() + 1)
+                };
+                let config = ApiConfig {
+                    enabled: true,
+                    port: 0,
+                    token: "s3cret".to_string(),
+                };
+                let api = Api::start(&config, state.clone(), Rc::new(RefCell::new(History::new())), on_change).unwrap();
+                let address = api.server.server_addr().to_ip().unwrap();
+
+                // The client waits on its own thread while this one runs the request
+                let request = |method: &'static str, path: &'static str, body: &'static str| {
+                    let client = thread::spawn(move || send(address, method, path, "s3cret", body));
+                    test_support::run_until(&context, || client.is_finished());
+                    client.join().unwrap()
+                };
+                let json = |body: &str| serde_json::from_str::<serde_json::Value>(body).unwrap();
+
+                assert_eq!(send(address, "GET", "/dates", "wrong", "").0, 401);
+
+                let date = "2026-02-19";
+                let (status, body) = request("POST", "/tasks", r#"{"text": "Задача", "date": "2026-02-19"}"#);
+                assert_eq!(status, 201, "{}", body);
+                assert_eq!(json(&body)["id"], 1);
+                let (status, body) = request("PATCH", "/tasks/1", r#"{"status": "Готово"}"#);
+                assert_eq!((status, json(&body)["status_label"].as_str()), (200, Some("Готово")));
+                assert!(state.borrow().get_row(date, 1).unwrap().status.is_done());
+
+                assert_eq!(json(&request("GET", "/dates", "").1), json!([date]));
+                let tasks = json(&request("GET", "/dates/2026-02-19/tasks", "").1);
+                assert_eq!(tasks[0]["text"], "Задача");
+                let (status, report) = request("GET", "/report?date=2026-02-19&format=markdown", "");
+                assert_eq!(status, 200);
+                assert!(report.contains("Задача"), "{}", report);
+                let report = json(&request("GET", "/report?from=2026-02-16&to=2026-02-20&format=json", "").1);
+                assert!(report.is_object(), "{}", report);
+
+                assert_eq!(request("PATCH", "/tasks/7", r#"{"text": "Другая"}"#).0, 404);
+                assert_eq!(request("POST", "/tasks", r#"{"text": "Задача", "date": "19.02.2026"}"#).0, 400);
+                assert_eq!(request("POST", "/tasks", r#"{"title": "Задача"}"#).0, 400);
+                assert_eq!(request("PUT", "/tasks/1", "").0, 405);
+                assert_eq!(request("GET", "/nowhere", "").0, 404);
+                let huge = format!(r#"{{"text": "{}"}}"#, "я".repeat(MAX_BODY as usize));
+                assert_eq!(send(address, "POST", "/tasks", "s3cret", &huge).0, 413);
+
+                assert_eq!(request("DELETE", "/tasks/1", "").0, 200);
+                assert!(state.borrow().locate(1).is_none());
+                assert_eq!(changes.get(), 3);
+            })
+            .unwrap();
+    }
+}
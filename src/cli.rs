@@ -0,0 +1,528 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use glib::ToVariant;
+use serde::Serialize;
+
+use gtk_reporter_core::config::Config;
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::paths;
+use gtk_reporter_core::report::{self, RangeGrouping};
+use gtk_reporter_core::state::{Row, RowChange, State, validate_date};
+use gtk_reporter_core::status::{self, Status};
+use gtk_reporter_core::template::{self, Template};
+
+use crate::dbus;
+use crate::instance::Instance;
+
+pub const USAGE: &str = "Команды (работают без окна; при открытом окне изменения проходят через него):
+  add <текст> [--date <дата>] [--status <статус>] [--blocked]
+  list [--date <дата>]
+  status <id> <статус>
+  move <id> <дата>
+  delete <id>
+  report [--date <дата> | --from <дата> --to <дата> [--merged]] [--template <название>]
+Дата по умолчанию — сегодня. --json выводит результат в JSON.";
+
+// Exit codes
+const EXIT_OK: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_NOT_FOUND: i32 = 3;
+const EXIT_INVALID: i32 = 4;
+
+// How long a change waits for another command to finish with the data
+const LOCK_WAIT: Duration = Duration::from_secs(3);
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+
+/// First arguments that run a command instead of opening the window.
+pub const COMMANDS: [&str; 6] = ["add", "list", "status", "move", "delete", "report"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Add {
+        text: String,
+        date: Option<String>,
+        status: Option<String>,
+        blocked: bool,
+    },
+    List {
+        date: Option<String>,
+    },
+    Status {
+        id: u32,
+        status: String,
+    },
+    Move {
+        id: u32,
+        date: String,
+    },
+    Delete {
+        id: u32,
+    },
+    Report {
+        date: Option<String>,
+        range: Option<(String, String)>,
+        merged: bool,
+        template: Option<String>,
+    },
+}
+
+/// A command that changes tasks, its arguments checked.
+enum Change {
+    Add {
+        text: String,
+        date: String,
+        status: Status,
+        blocked: bool,
+    },
+    Status {
+        id: u32,
+        status: Status,
+    },
+    Move {
+        id: u32,
+        date: String,
+    },
+    Delete {
+        id: u32,
+    },
+}
+
+/// What a change did, to print.
+enum Outcome {
+    Task(u32),
+    Deleted { id: u32, date: String, text: String },
+}
+
+/// Who writes the data for a change.
+enum Writer {
+    /// This command, holding the instance lock until it is done.
+    Direct(Instance),
+    /// The open window, which owns the data; the change goes to it over D-Bus.
+    Window,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Invocation {
+    command: Command,
+    json: bool,
+}
+
+fn parse_id(value: &str) -> Result<u32, String> {
+    value
+        .trim_start_matches('#')
+        .parse()
+        .map_err(|_| format!("Неверный номер задачи: {}", value))
+}
+
+fn parse(args: Vec<String>) -> Result<Invocation, String> {
+    let mut json = false;
+    let mut positional = Vec::new();
+    let mut options: Vec<(String, Option<String>)> = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--blocked" | "--merged" => options.push((arg, None)),
+            "--date" | "--status" | "--from" | "--to" | "--template" => {
+                let value = args.next().ok_or(format!("{}: не указано значение", arg))?;
+                options.push((arg, Some(value)));
+            }
+            // Everything after is text, even if it starts with a dash
+            "--" => positional.extend(args.by_ref()),
+            _ if arg.starts_with("--") => return Err(format!("Неизвестный аргумент: {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut option = |name: &str| {
+        let index = options.iter().position(|(option, _)| option == name)?;
+        Some(options.remove(index).1.unwrap_or_default())
+    };
+    let (name, rest) = positional.split_first().ok_or("Не указана команда")?;
+    let command = match (name.as_str(), rest) {
+        ("add", [text]) => Command::Add {
+            text: text.clone(),
+            date: option("--date"),
+            status: option("--status"),
+            blocked: option("--blocked").is_some(),
+        },
+        ("list", []) => Command::List { date: option("--date") },
+        ("status", [id, status]) => Command::Status {
+            id: parse_id(id)?,
+            status: status.clone(),
+        },
+        ("move", [id, date]) => Command::Move {
+            id: parse_id(id)?,
+            date: date.clone(),
+        },
+        ("delete", [id]) => Command::Delete { id: parse_id(id)? },
+        ("report", []) => {
+            let range = match (option("--from"), option("--to")) {
+                (Some(from), Some(to)) => Some((from, to)),
+                (None, None) => None,
+                _ => return Err("report: --from и --to указываются вместе".to_string()),
+            };
+            let date = option("--date");
+            if date.is_some() && range.is_some() {
+                return Err("report: укажите либо --date, либо --from и --to".to_string());
+            }
+            Command::Report {
+                date,
+                range,
+                merged: option("--merged").is_some(),
+                template: option("--template"),
+            }
+        }
+        (name, _) if COMMANDS.contains(&name) => return Err(format!("{}: неверные аргументы", name)),
+        (name, _) => return Err(format!("Неизвестная команда: {}", name)),
+    };
+    if let Some((name, _)) = options.first() {
+        return Err(format!("{}: не подходит к этой команде", name));
+    }
+    Ok(Invocation { command, json })
+}
+
+fn exit_code(err: &ReporterError) -> i32 {
+    match err {
+        ReporterError::DateNotFound | ReporterError::RowNotFound => EXIT_NOT_FOUND,
+        ReporterError::EmptyText
+        | ReporterError::InvalidDate(_)
+        | ReporterError::InvalidRange { .. }
+        | ReporterError::UnknownStatus(_)
+        | ReporterError::Template(_)
+        | ReporterError::TemplateName(_) => EXIT_INVALID,
+        _ => EXIT_FAILURE,
+    }
+}
+
+/// A task as printed with `--json`, and as the HTTP API returns it.
+#[derive(Serialize)]
+pub struct TaskJson<'a> {
+    date: &'a str,
+    #[serde(flatten)]
+    row: &'a Row,
+    status_label: &'a str,
+}
+
+impl<'a> TaskJson<'a> {
+    pub fn new(date: &'a str, row: &'a Row) -> Self {
+        Self {
+            date,
+            row,
+            status_label: row.status.label(),
+        }
+    }
+}
+
+fn print_tasks(tasks: &[(String, Row)], json: bool, out: &mut dyn Write) -> Result<(), ReporterError> {
+    if json {
+        let tasks: Vec<TaskJson> = tasks.iter().map(|(date, row)| TaskJson::new(date, row)).collect();
+        writeln!(out, "{}", serde_json::to_string_pretty(&tasks)?)?;
+    } else {
+        for (date, row) in tasks {
+            let blocked = if row.blocked { " ⛔" } else { "" };
+            writeln!(out, "#{}\t{}\t{}{}\t{}", row.id, date, row.status.label(), blocked, row.text)?;
+        }
+    }
+    Ok(())
+}
+
+fn find_status(name: &str) -> Result<Status, ReporterError> {
+    Status::find(name).ok_or_else(|| ReporterError::UnknownStatus(name.to_string()))
+}
+
+/// Loads the data, writing an upgraded file back only when `locked`.
+fn load(locked: bool) -> Result<State, ReporterError> {
+    let loaded = if locked { State::load() } else { State::peek() };
+    match loaded {
+        Ok(state) => Ok(state),
+        Err(err) if err.is_not_found() => Ok(State::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Takes the instance lock, waiting a little for another command to let go
+/// of it. A window holds it until closed, so changes then go through it, if
+/// it is the one working on our data directory.
+fn writer() -> Result<Writer, ReporterError> {
+    let deadline = Instant::now() + LOCK_WAIT;
+    loop {
+        let instance = Instance::acquire()?;
+        if instance.is_primary() {
+            return Ok(Writer::Direct(instance));
+        }
+        if dbus::window_available(&paths::data_dir()?) {
+            return Ok(Writer::Window);
+        }
+        if Instant::now() >= deadline {
+            return Err(ReporterError::AlreadyRunning);
+        }
+        std::thread::sleep(LOCK_RETRY);
+    }
+}
+
+/// Changes a task in one saved, journaled transaction.
+fn update(state: &mut State, id: u32, status: Option<Status>, new_date: Option<String>) -> Result<Outcome, ReporterError> {
+    let (date, _) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+    state.transaction(|s| {
+        s.apply(RowChange::Update {
+            date,
+            id,
+            text: None,
+            status,
+            blocked: None,
+            new_date,
+        })
+    })?;
+    Ok(Outcome::Task(id))
+}
+
+fn apply_directly(change: Change, state: &mut State) -> Result<Outcome, ReporterError> {
+    match change {
+        Change::Add { text, date, status, blocked } => {
+            let change = RowChange::Create { date, text, status, blocked };
+            Ok(Outcome::Task(state.transaction(|s| s.apply(change))?.id))
+        }
+        Change::Status { id, status } => update(state, id, Some(status), None),
+        Change::Move { id, date } => update(state, id, None, Some(date)),
+        Change::Delete { id } => {
+            let (date, row) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            state.transaction(|s| s.delete_row(date.clone(), id))?;
+            Ok(Outcome::Deleted { id, date, text: row.text })
+        }
+    }
+}
+
+/// Hands the change to the open window. `state` is only read, to answer
+/// for missing tasks the same way as without the window.
+fn apply_in_window(change: Change, state: &State) -> Result<Outcome, ReporterError> {
+    match change {
+        Change::Add { text, date, status, blocked } => {
+            let reply = dbus::call_window("AddTask", (date, text, status.id()).to_variant())?;
+            let (id,) = reply.get::<(u32,)>().ok_or_else(|| std::io::Error::other("Окно вернуло неожиданный ответ"))?;
+            if blocked {
+                dbus::call_window("SetBlocked", (id, true).to_variant())?;
+            }
+            Ok(Outcome::Task(id))
+        }
+        Change::Status { id, status } => {
+            state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            dbus::call_window("SetStatus", (id, status.id()).to_variant())?;
+            Ok(Outcome::Task(id))
+        }
+        Change::Move { id, date } => {
+            state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            dbus::call_window("MoveTask", (id, date).to_variant())?;
+            Ok(Outcome::Task(id))
+        }
+        Change::Delete { id } => {
+            let (date, row) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            dbus::call_window("DeleteTask", (id,).to_variant())?;
+            Ok(Outcome::Deleted { id, date, text: row.text })
+        }
+    }
+}
+
+fn print_outcome(outcome: Outcome, state: &State, json: bool, out: &mut dyn Write) -> Result<(), ReporterError> {
+    match outcome {
+        Outcome::Task(id) => {
+            let task = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            print_tasks(&[task], json, out)
+        }
+        Outcome::Deleted { id, date, text } => {
+            if json {
+                writeln!(out, "{}", serde_json::json!({ "deleted": id, "date": date }))?;
+            } else {
+                writeln!(out, "Удалена задача #{}: {}", id, text)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn execute(invocation: Invocation, out: &mut dyn Write) -> Result<(), ReporterError> {
+    let json = invocation.json;
+    let change = match invocation.command {
+        Command::Add { text, date, status, blocked } => {
+            let date = date.unwrap_or_else(today);
+            validate_date(&date)?;
+            Change::Add {
+                text,
+                date,
+                status: status.as_deref().map(find_status).transpose()?.unwrap_or_else(Status::initial),
+                blocked,
+            }
+        }
+        Command::Status { id, status } => Change::Status {
+            id,
+            status: find_status(&status)?,
+        },
+        Command::Move { id, date } => {
+            validate_date(&date)?;
+            Change::Move { id, date }
+        }
+        Command::Delete { id } => Change::Delete { id },
+        Command::List { date } => {
+            let date = date.unwrap_or_else(today);
+            validate_date(&date)?;
+            let rows = load(false)?.get_rows_for_date(&date);
+            return print_tasks(&rows.into_iter().map(|row| (date.clone(), row)).collect::<Vec<_>>(), json, out);
+        }
+        Command::Report { date, range, merged, template } => {
+            let state = load(false)?;
+            let ctx = match range {
+                Some((from, to)) => {
+                    let grouping = if merged { RangeGrouping::Merged } else { RangeGrouping::ByDay };
+                    report::range_context(&state, &from, &to, grouping)?
+                }
+                None => {
+                    let date = date.unwrap_or_else(today);
+                    validate_date(&date)?;
+                    report::day_context(&state, &date)
+                }
+            };
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&template::to_json(&ctx))?)?;
+                return Ok(());
+            }
+            let template = match template {
+                Some(name) => template::load_all()?
+                    .into_iter()
+                    .find(|template| template.name == name)
+                    .ok_or(ReporterError::TemplateName(name))?,
+                None => Template::standard(),
+            };
+            write!(out, "{}", template.render(&ctx)?)?;
+            return Ok(());
+        }
+    };
+
+    match writer()? {
+        // Loaded under the lock, so nobody writes in between
+        Writer::Direct(_lock) => {
+            let mut state = load(true)?;
+            let outcome = apply_directly(change, &mut state)?;
+            print_outcome(outcome, &state, json, out)
+        }
+        Writer::Window => {
+            let outcome = apply_in_window(change, &load(false)?)?;
+            print_outcome(outcome, &load(false)?, json, out)
+        }
+    }
+}
+
+/// Runs a command given on the command line and returns the exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let invocation = match parse(args) {
+        Ok(invocation) => invocation,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    let json = invocation.json;
+
+    let statuses = Config::load().map_or_else(
+        |err| {
+            eprintln!("Не удалось прочитать настройки: {}", err);
+            Vec::new()
+        },
+        |config| config.statuses,
+    );
+    if let Err(err) = status::init(statuses) {
+        eprintln!("{}", err);
+    }
+    match execute(invocation, &mut std::io::stdout().lock()) {
+        Ok(()) => EXIT_OK,
+        Err(err) => {
+            if json {
+                eprintln!("{}", serde_json::json!({ "error": err.to_string() }));
+            } else {
+                eprintln!("{}", err);
+            }
+            exit_code(&err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_rejects_misuse() {
+        assert_eq!(
+            parse(args("add --date 2026-02-19 --json -- --флаг")).unwrap(),
+            Invocation {
+                command: Command::Add {
+                    text: "--флаг".to_string(),
+                    date: Some("2026-02-19".to_string()),
+                    status: None,
+                    blocked: false,
+                },
+                json: true,
+            }
+        );
+        assert_eq!(
+            parse(args("status #12 ready")).unwrap().command,
+            Command::Status {
+                id: 12,
+                status: "ready".to_string()
+            }
+        );
+        assert!(parse(args("report --from 2026-02-16")).is_err());
+        assert!(parse(args("report --date 2026-02-16 --from 2026-02-16 --to 2026-02-20")).is_err());
+        assert!(parse(args("list --status ready")).is_err());
+        assert!(parse(args("delete")).is_err());
+        assert!(parse(args("frobnicate")).is_err());
+    }
+
+    fn run_command(line: &str) -> Result<String, ReporterError> {
+        let mut out = Vec::new();
+        execute(parse(args(line)).unwrap(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn changes_are_saved_and_printed() {
+        let _data_dir = test_support::data_dir();
+        // Other tests share the data, so ids come from the output
+        let added: serde_json::Value =
+            serde_json::from_str(&run_command("add Задача --date 2031-05-05 --blocked --json").unwrap()).unwrap();
+        assert_eq!(added[0]["date"], "2031-05-05");
+        assert_eq!(added[0]["text"], "Задача");
+        assert_eq!(added[0]["blocked"], true);
+        let id = added[0]["id"].as_u64().unwrap() as u32;
+
+        let moved = run_command(&format!("move {} 2031-05-06", id)).unwrap();
+        assert!(moved.starts_with(&format!("#{}\t2031-05-06\t", id)), "{}", moved);
+        run_command(&format!("status #{} ready", id)).unwrap();
+        let (date, row) = State::load().unwrap().locate(id).unwrap();
+        assert_eq!(date, "2031-05-06");
+        assert!(row.status.is_done());
+        let listed: serde_json::Value = serde_json::from_str(&run_command("list --date 2031-05-06 --json").unwrap()).unwrap();
+        assert_eq!(listed[0]["id"], id);
+
+        assert_eq!(run_command(&format!("delete {}", id)).unwrap(), format!("Удалена задача #{}: Задача\n", id));
+        assert!(State::load().unwrap().locate(id).is_none());
+
+        let missing = run_command(&format!("delete {}", id)).unwrap_err();
+        assert_eq!(exit_code(&missing), EXIT_NOT_FOUND);
+        let unknown = run_command("add Задача --status нет-такого").unwrap_err();
+        assert_eq!(exit_code(&unknown), EXIT_INVALID);
+        let invalid = run_command("list --date 05.05.2031").unwrap_err();
+        assert_eq!(exit_code(&invalid), EXIT_INVALID);
+    }
+}
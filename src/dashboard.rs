@@ -0,0 +1,320 @@
+use std::io;
+use std::path::Path;
+
+use cairo::{Context, FontSlant, FontWeight};
+use chrono::Duration;
+
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::stats::Stats;
+use gtk_reporter_core::status::Status;
+
+/// Size of exported charts.
+pub const WIDTH: f64 = 640.0;
+pub const HEIGHT: f64 = 320.0;
+
+const MARGIN: f64 = 28.0;
+const TEXT: (f64, f64, f64) = (0.24, 0.24, 0.26);
+const GRID: (f64, f64, f64) = (0.85, 0.85, 0.87);
+const ACCENT: (f64, f64, f64) = (0.21, 0.52, 0.89);
+const DONE: (f64, f64, f64) = (0.15, 0.64, 0.41);
+const UNKNOWN: (f64, f64, f64) = (0.6, 0.6, 0.6);
+
+/// The charts on the statistics screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chart {
+    PerDay,
+    Statuses,
+    LeadTime,
+    Streaks,
+}
+
+impl Chart {
+    pub const ALL: [Self; 4] = [Self::PerDay, Self::Statuses, Self::LeadTime, Self::Streaks];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::PerDay => "Задач по дням",
+            Self::Statuses => "Статусы по неделям",
+            Self::LeadTime => "Среднее время до готовности",
+            Self::Streaks => "Серии дней с готовыми задачами",
+        }
+    }
+
+    /// Suggested file name for exporting, without the extension.
+    pub fn file_stem(self) -> &'static str {
+        match self {
+            Self::PerDay => "tasks-per-day",
+            Self::Statuses => "statuses-per-week",
+            Self::LeadTime => "lead-time",
+            Self::Streaks => "streaks",
+        }
+    }
+}
+
+/// `#rrggbb` as cairo components; anything else is grey.
+fn parse_colour(colour: &str) -> (f64, f64, f64) {
+    let hex = colour.strip_prefix('#').filter(|hex| hex.len() == 6);
+    let channel = |i: usize| {
+        hex.and_then(|hex| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .map(|value| f64::from(value) / 255.0)
+    };
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) => (r, g, b),
+        _ => UNKNOWN,
+    }
+}
+
+fn set_colour(cr: &Context, (r, g, b): (f64, f64, f64)) {
+    cr.set_source_rgb(r, g, b);
+}
+
+fn label(cr: &Context, text: &str, x: f64, y: f64, centred: bool) -> Result<(), cairo::Error> {
+    let x = if centred { x - cr.text_extents(text)?.width() / 2.0 } else { x };
+    cr.move_to(x, y);
+    cr.show_text(text)
+}
+
+/// "1.5 ч" or, from a day on, "2.3 дн.".
+pub fn format_duration(duration: Duration) -> String {
+    let hours = duration.num_minutes() as f64 / 60.0;
+    if hours >= 24.0 {
+        format!("{:.1} дн.", hours / 24.0)
+    } else {
+        format!("{:.1} ч", hours)
+    }
+}
+
+/// Plot area below the title and above the axis labels.
+struct Area {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Area {
+    fn bottom(&self) -> f64 {
+        self.top + self.height
+    }
+
+    /// Left edge and width of bar `i` of `count`.
+    fn slot(&self, i: usize, count: usize) -> (f64, f64) {
+        let step = self.width / count.max(1) as f64;
+        (self.left + step * i as f64 + step * 0.15, step * 0.7)
+    }
+}
+
+fn axis(cr: &Context, area: &Area, max: f64, unit: &str) -> Result<(), cairo::Error> {
+    set_colour(cr, GRID);
+    cr.set_line_width(1.0);
+    for step in 0..=4 {
+        let y = area.bottom() - area.height * f64::from(step) / 4.0;
+        cr.move_to(area.left, y);
+        cr.line_to(area.left + area.width, y);
+    }
+    cr.stroke()?;
+    set_colour(cr, TEXT);
+    label(cr, &format!("{}{}", format_max(max), unit), 4.0, area.top + 4.0, false)
+}
+
+fn format_max(max: f64) -> String {
+    if max.fract() == 0.0 { format!("{}", max) } else { format!("{:.1}", max) }
+}
+
+/// Draws `chart` filling a `width` × `height` surface.
+pub fn draw(cr: &Context, chart: Chart, stats: &Stats, width: f64, height: f64) -> Result<(), cairo::Error> {
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.paint()?;
+    cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Normal);
+    set_colour(cr, TEXT);
+    cr.set_font_size(13.0);
+    label(cr, chart.title(), MARGIN, 18.0, false)?;
+    cr.set_font_size(10.0);
+
+    let area = Area {
+        left: MARGIN + 12.0,
+        top: MARGIN + 8.0,
+        width: (width - 2.0 * MARGIN - 12.0).max(1.0),
+        height: (height - 2.0 * MARGIN - 20.0).max(1.0),
+    };
+    match chart {
+        Chart::PerDay => draw_per_day(cr, &area, stats),
+        Chart::Statuses => draw_statuses(cr, &area, stats),
+        Chart::LeadTime => draw_lead_time(cr, &area, stats),
+        Chart::Streaks => draw_streaks(cr, &area, stats),
+    }
+}
+
+fn draw_per_day(cr: &Context, area: &Area, stats: &Stats) -> Result<(), cairo::Error> {
+    let max = stats.per_day.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1) as f64;
+    axis(cr, area, max, "")?;
+    let count = stats.per_day.len();
+    for (i, (day, tasks)) in stats.per_day.iter().enumerate() {
+        let (x, bar) = area.slot(i, count);
+        let h = area.height * *tasks as f64 / max;
+        set_colour(cr, ACCENT);
+        cr.rectangle(x, area.bottom() - h, bar, h);
+        cr.fill()?;
+        // A date every week, counting back from the last day
+        if (count - 1 - i).is_multiple_of(7) {
+            set_colour(cr, TEXT);
+            label(cr, &day.format("%d.%m").to_string(), x + bar / 2.0, area.bottom() + 14.0, true)?;
+        }
+    }
+    Ok(())
+}
+
+fn draw_statuses(cr: &Context, area: &Area, stats: &Stats) -> Result<(), cairo::Error> {
+    let total = |counts: &[(Status, usize)]| counts.iter().map(|(_, n)| n).sum::<usize>();
+    let max = stats.per_week.iter().map(|(_, counts)| total(counts)).max().unwrap_or(0).max(1) as f64;
+    axis(cr, area, max, "")?;
+    let count = stats.per_week.len();
+    for (i, (week, counts)) in stats.per_week.iter().enumerate() {
+        let (x, bar) = area.slot(i, count);
+        let mut y = area.bottom();
+        for (status, tasks) in counts {
+            let h = area.height * *tasks as f64 / max;
+            set_colour(cr, parse_colour(status.colour()));
+            cr.rectangle(x, y - h, bar, h);
+            cr.fill()?;
+            y -= h;
+        }
+        if i % 2 == count % 2 {
+            set_colour(cr, TEXT);
+            label(cr, &week.format("%d.%m").to_string(), x + bar / 2.0, area.bottom() + 14.0, true)?;
+        }
+    }
+
+    // Legend of the statuses shown, in their order
+    let mut shown: Vec<&Status> = stats
+        .per_week
+        .iter()
+        .flat_map(|(_, counts)| counts.iter().map(|(status, _)| status))
+        .collect();
+    shown.sort_by_key(|status| status.order());
+    shown.dedup();
+    let mut x = area.left + area.width;
+    for status in shown.iter().rev() {
+        x -= cr.text_extents(status.label())?.x_advance() + 24.0;
+        set_colour(cr, parse_colour(status.colour()));
+        cr.rectangle(x, 10.0, 10.0, 10.0);
+        cr.fill()?;
+        set_colour(cr, TEXT);
+        label(cr, status.label(), x + 14.0, 19.0, false)?;
+    }
+    Ok(())
+}
+
+fn draw_lead_time(cr: &Context, area: &Area, stats: &Stats) -> Result<(), cairo::Error> {
+    let hours = |lead: Duration| lead.num_minutes() as f64 / 60.0;
+    let max_hours = stats.lead_time.iter().filter_map(|(_, lead)| lead.map(hours)).fold(0.0, f64::max);
+    // Days once any week took more than one
+    let (scale, unit) = if max_hours > 24.0 { (24.0, " дн.") } else { (1.0, " ч") };
+    let max = (max_hours / scale).ceil().max(1.0);
+    axis(cr, area, max, unit)?;
+    let count = stats.lead_time.len();
+    for (i, (week, lead)) in stats.lead_time.iter().enumerate() {
+        let (x, bar) = area.slot(i, count);
+        if let Some(lead) = lead {
+            let h = area.height * hours(*lead) / scale / max;
+            set_colour(cr, DONE);
+            cr.rectangle(x, area.bottom() - h, bar, h);
+            cr.fill()?;
+        }
+        if i % 2 == count % 2 {
+            set_colour(cr, TEXT);
+            label(cr, &week.format("%d.%m").to_string(), x + bar / 2.0, area.bottom() + 14.0, true)?;
+        }
+    }
+    let overall = stats
+        .lead_time_overall
+        .map_or("нет данных".to_string(), format_duration);
+    set_colour(cr, TEXT);
+    label(cr, &format!("В среднем: {}", overall), area.left + area.width / 2.0, 19.0, false)
+}
+
+fn draw_streaks(cr: &Context, area: &Area, stats: &Stats) -> Result<(), cairo::Error> {
+    let max = stats.done_per_day.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1) as f64;
+    let count = stats.done_per_day.len();
+    let cell = (area.width / count.max(1) as f64).min(area.height / 2.0);
+    let top = area.top + (area.height - cell) / 2.0;
+    for (i, (day, done)) in stats.done_per_day.iter().enumerate() {
+        let x = area.left + cell * i as f64;
+        // Paler for fewer finished tasks, grid colour for none
+        let strength = *done as f64 / max;
+        let (r, g, b) = DONE;
+        if *done == 0 {
+            set_colour(cr, GRID);
+        } else {
+            cr.set_source_rgb(1.0 - (1.0 - r) * strength, 1.0 - (1.0 - g) * strength, 1.0 - (1.0 - b) * strength);
+        }
+        cr.rectangle(x + 1.0, top, cell - 2.0, cell);
+        cr.fill()?;
+        if (count - 1 - i).is_multiple_of(7) {
+            set_colour(cr, TEXT);
+            label(cr, &day.format("%d.%m").to_string(), x + cell / 2.0, top + cell + 14.0, true)?;
+        }
+    }
+    set_colour(cr, TEXT);
+    cr.set_font_size(12.0);
+    label(
+        cr,
+        &format!("Текущая серия: {} дн.   Рекорд: {} дн.", stats.current_streak, stats.longest_streak),
+        area.left,
+        top - 12.0,
+        false,
+    )
+}
+
+/// Saves `chart` as SVG for a `.svg` path and as PNG otherwise.
+pub fn export(chart: Chart, stats: &Stats, path: &Path) -> Result<(), ReporterError> {
+    let drawing = |err: cairo::Error| io::Error::other(format!("Не удалось нарисовать график: {}", err));
+    let svg = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if svg {
+        let surface = cairo::SvgSurface::new(WIDTH, HEIGHT, Some(path)).map_err(drawing)?;
+        draw(&Context::new(&surface).map_err(drawing)?, chart, stats, WIDTH, HEIGHT).map_err(drawing)?;
+        surface.finish();
+    } else {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, WIDTH as i32, HEIGHT as i32).map_err(drawing)?;
+        draw(&Context::new(&surface).map_err(drawing)?, chart, stats, WIDTH, HEIGHT).map_err(drawing)?;
+        let mut file = std::fs::File::create(path)?;
+        surface
+            .write_to_png(&mut file)
+            .map_err(|err| io::Error::other(format!("Не удалось сохранить график: {}", err)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use gtk_reporter_core::state::{RowChange, State};
+    use gtk_reporter_core::stats;
+
+    use crate::test_support;
+
+    #[test]
+    fn exports_every_chart_as_png_and_svg() {
+        test_support::statuses();
+        let mut state = State::new();
+        state
+            .apply(RowChange::Create {
+                date: "2026-02-19".to_string(),
+                text: "Задача".to_string(),
+                status: Status::initial(),
+                blocked: false,
+            })
+            .unwrap();
+        let stats = stats::collect(&state, NaiveDate::from_ymd_opt(2026, 2, 20).unwrap(), &[]);
+        let dir = tempfile::tempdir().unwrap();
+        for chart in Chart::ALL {
+            let png = dir.path().join(format!("{}.png", chart.file_stem()));
+            export(chart, &stats, &png).unwrap();
+            assert!(std::fs::read(&png).unwrap().starts_with(b"\x89PNG"));
+            let svg = dir.path().join(format!("{}.SVG", chart.file_stem()));
+            export(chart, &stats, &svg).unwrap();
+            assert!(std::fs::read_to_string(&svg).unwrap().contains("<svg"));
+        }
+    }
+}
@@ -0,0 +1,546 @@
+use glib::thread_guard::ThreadGuard;
+use gtk::gio;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::sync::Mutex;
+
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::history::{Command, History};
+use gtk_reporter_core::paths;
+use gtk_reporter_core::state::{RowChange, RowDiff, State, validate_date};
+use gtk_reporter_core::status::Status;
+
+pub const BUS_NAME: &str = "org.gtkreporter.Tasks";
+pub const OBJECT_PATH: &str = "/org/gtkreporter/Tasks";
+pub const INTERFACE: &str = "org.gtkreporter.Tasks";
+
+const ERROR_NOT_FOUND: &str = "org.gtkreporter.Tasks.Error.NotFound";
+const ERROR_READ_ONLY: &str = "org.gtkreporter.Tasks.Error.ReadOnly";
+const ERROR_BUSY: &str = "org.gtkreporter.Tasks.Error.Busy";
+const ERROR_INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const ERROR_FAILED: &str = "org.freedesktop.DBus.Error.Failed";
+
+// An empty date means today and an empty status the initial one.
+// ListTasks returns (id, text, status id, status label, blocked).
+const INTROSPECTION: &str = r#"<node>
+  <interface name="org.gtkreporter.Tasks">
+    <method name="AddTask">
+      <arg name="date" type="s" direction="in"/>
+      <arg name="text" type="s" direction="in"/>
+      <arg name="status" type="s" direction="in"/>
+      <arg name="id" type="u" direction="out"/>
+    </method>
+    <method name="ListTasks">
+      <arg name="date" type="s" direction="in"/>
+      <arg name="tasks" type="a(usssb)" direction="out"/>
+    </method>
+    <method name="SetStatus">
+      <arg name="id" type="u" direction="in"/>
+      <arg name="status" type="s" direction="in"/>
+    </method>
+    <method name="SetBlocked">
+      <arg name="id" type="u" direction="in"/>
+      <arg name="blocked" type="b" direction="in"/>
+    </method>
+    <method name="MoveTask">
+      <arg name="id" type="u" direction="in"/>
+      <arg name="date" type="s" direction="in"/>
+    </method>
+    <method name="DeleteTask">
+      <arg name="id" type="u" direction="in"/>
+    </method>
+    <method name="DataDir">
+      <arg name="path" type="s" direction="out"/>
+    </method>
+    <method name="GenerateReport">
+      <arg name="date" type="s" direction="in"/>
+      <arg name="report" type="s" direction="out"/>
+    </method>
+    <signal name="TaskAdded">
+      <arg name="id" type="u"/>
+      <arg name="date" type="s"/>
+    </signal>
+    <signal name="TaskChanged">
+      <arg name="id" type="u"/>
+      <arg name="date" type="s"/>
+    </signal>
+    <signal name="TaskRemoved">
+      <arg name="id" type="u"/>
+      <arg name="date" type="s"/>
+    </signal>
+  </interface>
+</node>"#;
+
+/// GIO wants callbacks it may call from any thread, but ours only ever run on
+/// the main loop they were registered from, so they can keep `Rc`s.
+type MainThread<T> = Mutex<ThreadGuard<T>>;
+
+fn error_name(err: &ReporterError) -> &'static str {
+    match err {
+        ReporterError::DateNotFound | ReporterError::RowNotFound => ERROR_NOT_FOUND,
+        ReporterError::EmptyText | ReporterError::InvalidDate(_) | ReporterError::UnknownStatus(_) => ERROR_INVALID_ARGS,
+        ReporterError::ReadOnly => ERROR_READ_ONLY,
+        _ => ERROR_FAILED,
+    }
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn date_or_today(date: String) -> Result<String, ReporterError> {
+    let date = if date.is_empty() { today() } else { date };
+    validate_date(&date)?;
+    Ok(date)
+}
+
+fn find_status(name: &str) -> Result<Status, ReporterError> {
+    Status::find(name).ok_or_else(|| ReporterError::UnknownStatus(name.to_string()))
+}
+
+/// Changes the given fields of task `id`, wherever it is.
+fn update(
+    state: &mut State,
+    history: &mut History,
+    id: u32,
+    status: Option<Status>,
+    blocked: Option<bool>,
+    new_date: Option<String>,
+) -> Result<(glib::Variant, bool), ReporterError> {
+    let (date, _) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+    let change = RowChange::Update {
+        date,
+        id,
+        text: None,
+        status,
+        blocked,
+        new_date,
+    };
+    history.execute(state, Command::Apply(change))?;
+    Ok((().to_variant(), true))
+}
+
+/// Runs one method. Returns the reply and whether the tasks changed.
+fn call(
+    state: &mut State,
+    history: &mut History,
+    method: &str,
+    parameters: &glib::Variant,
+) -> Result<(glib::Variant, bool), ReporterError> {
+    // GDBus has already checked the arguments against the introspection data
+    match method {
+        "AddTask" => {
+            let (date, text, status) = parameters.get::<(String, String, String)>().unwrap_or_default();
+            let status = if status.is_empty() { Status::initial() } else { find_status(&status)? };
+            let change = RowChange::Create {
+                date: date_or_today(date)?,
+                text,
+                status,
+                blocked: false,
+            };
+            let rows = history.execute(state, Command::Apply(change))?;
+            let id = rows.first().map_or(0, |row| row.id);
+            Ok(((id,).to_variant(), true))
+        }
+        "ListTasks" => {
+            let (date,) = parameters.get::<(String,)>().unwrap_or_default();
+            let tasks: Vec<(u32, String, String, String, bool)> = state
+                .get_rows_for_date(&date_or_today(date)?)
+                .into_iter()
+                .map(|row| (row.id, row.text, row.status.id().to_string(), row.status.label().to_string(), row.blocked))
+                .collect();
+            Ok(((tasks,).to_variant(), false))
+        }
+        "SetStatus" => {
+            let (id, status) = parameters.get::<(u32, String)>().unwrap_or_default();
+            update(state, history, id, Some(find_status(&status)?), None, None)
+        }
+        "SetBlocked" => {
+            let (id, blocked) = parameters.get::<(u32, bool)>().unwrap_or_default();
+            update(state, history, id, None, Some(blocked), None)
+        }
+        "MoveTask" => {
+            let (id, date) = parameters.get::<(u32, String)>().unwrap_or_default();
+            update(state, history, id, None, None, Some(date_or_today(date)?))
+        }
+        "DeleteTask" => {
+            let (id,) = parameters.get::<(u32,)>().unwrap_or_default();
+            let (date, _) = state.locate(id).ok_or(ReporterError::RowNotFound)?;
+            history.execute(state, Command::Delete { date, id })?;
+            Ok((().to_variant(), true))
+        }
+        "DataDir" => {
+            let dir = canonical(paths::data_dir()?);
+            Ok(((dir.to_string_lossy().to_string(),).to_variant(), false))
+        }
+        "GenerateReport" => {
+            let (date,) = parameters.get::<(String,)>().unwrap_or_default();
+            let report = state.generate_report(&date_or_today(date)?);
+            Ok(((report,).to_variant(), false))
+        }
+        _ => unreachable!("GDBus only passes on methods of the interface"),
+    }
+}
+
+/// The error the window answered with, as the one it started from where
+/// that matters to the caller.
+fn window_error(err: glib::Error) -> ReporterError {
+    let message = err.message();
+    // GDBus passes the remote error name on as a prefix of the message
+    let (name, text) = message
+        .strip_prefix("GDBus.Error:")
+        .and_then(|rest| rest.split_once(": "))
+        .unwrap_or(("", message));
+    match name {
+        ERROR_NOT_FOUND => ReporterError::RowNotFound,
+        ERROR_READ_ONLY => ReporterError::ReadOnly,
+        "org.freedesktop.DBus.Error.ServiceUnknown" | "org.freedesktop.DBus.Error.NameHasNoOwner" => {
+            ReporterError::AlreadyRunning
+        }
+        _ => ReporterError::IoError(std::io::Error::other(text.to_string())),
+    }
+}
+
+fn session_bus() -> Option<gio::DBusConnection> {
+    gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE).ok()
+}
+
+fn canonical(dir: PathBuf) -> PathBuf {
+    std::fs::canonicalize(&dir).unwrap_or(dir)
+}
+
+/// Whether `destination` is a window working on `data_dir`. The bus name is
+/// the same for every data directory, so it has to be asked.
+fn serves(connection: &gio::DBusConnection, destination: &str, data_dir: &Path) -> bool {
+    connection
+        .call_sync(
+            Some(destination),
+            OBJECT_PATH,
+            INTERFACE,
+            "DataDir",
+            None,
+            None,
+            gio::DBusCallFlags::NO_AUTO_START,
+            -1,
+            gio::Cancellable::NONE,
+        )
+        .ok()
+        .and_then(|reply| reply.get::<(String,)>())
+        .is_some_and(|(dir,)| Path::new(&dir) == canonical(data_dir.to_path_buf()))
+}
+
+/// Whether the window on the session bus works on `data_dir`.
+pub fn window_available(data_dir: &Path) -> bool {
+    session_bus().is_some_and(|connection| serves(&connection, BUS_NAME, data_dir))
+}
+
+/// Runs `method` in the open window, for changes made while it owns the data.
+pub fn call_window(method: &str, parameters: glib::Variant) -> Result<glib::Variant, ReporterError> {
+    let connection = session_bus().ok_or(ReporterError::AlreadyRunning)?;
+    connection
+        .call_sync(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            INTERFACE,
+            method,
+            Some(&parameters),
+            None,
+            gio::DBusCallFlags::NO_AUTO_START,
+            -1,
+            gio::Cancellable::NONE,
+        )
+        .map_err(window_error)
+}
+
+/// The signal describing one changed task.
+fn signal(diff: &RowDiff) -> Option<(&'static str, u32, &str)> {
+    match (&diff.before, &diff.after) {
+        (None, Some((date, _))) => Some(("TaskAdded", diff.id, date)),
+        (Some(_), Some((date, _))) => Some(("TaskChanged", diff.id, date)),
+        (Some((date, _)), None) => Some(("TaskRemoved", diff.id, date)),
+        (None, None) => None,
+    }
+}
+
+/// The `org.gtkreporter.Tasks` interface, working on the window's own tasks so
+/// that other programs don't have to race it on the data file.
+pub struct Service {
+    state: Rc<RefCell<State>>,
+    history: Rc<RefCell<History>>,
+    on_change: Box<dyn Fn()>,
+    connection: RefCell<Option<gio::DBusConnection>>,
+}
+
+impl Service {
+    /// `on_change` is called after a method has changed the tasks. Whatever
+    /// goes through `history` is signalled, whoever did it.
+    pub fn new<F: Fn() + 'static>(state: Rc<RefCell<State>>, history: Rc<RefCell<History>>, on_change: F) -> Rc<Self> {
+        Rc::new_cyclic(|service: &Weak<Self>| {
+            let service = service.clone();
+            history.borrow_mut().add_listener(move |diffs| {
+                if let Some(service) = service.upgrade() {
+                    service.announce(diffs);
+                }
+            });
+            Self {
+                state,
+                history,
+                on_change: Box::new(on_change),
+                connection: RefCell::new(None),
+            }
+        })
+    }
+
+    /// Serves the interface on `connection` at `OBJECT_PATH`.
+    pub fn register(self: &Rc<Self>, connection: &gio::DBusConnection) -> Result<gio::RegistrationId, glib::Error> {
+        let info = gio::DBusNodeInfo::for_xml(INTROSPECTION)?
+            .lookup_interface(INTERFACE)
+            .expect("the introspection data describes the interface");
+        let service: MainThread<Rc<Self>> = Mutex::new(ThreadGuard::new(self.clone()));
+        let id = connection.register_object(
+            OBJECT_PATH,
+            &info,
+            move |_, _, _, _, method, parameters, invocation| {
+                if let Ok(service) = service.lock() {
+                    service.get_ref().method_call(method, &parameters, invocation);
+                }
+            },
+            |_, _, _, _, _| ().to_variant(),
+            |_, _, _, _, _, _| false,
+        )?;
+        *self.connection.borrow_mut() = Some(connection.clone());
+        Ok(id)
+    }
+
+    /// Takes `BUS_NAME` on the session bus, where the interface then appears.
+    pub fn own_name(self: &Rc<Self>) -> gio::OwnerId {
+        let service: MainThread<Rc<Self>> = Mutex::new(ThreadGuard::new(self.clone()));
+        gio::bus_own_name(
+            gio::BusType::Session,
+            BUS_NAME,
+            gio::BusNameOwnerFlags::NONE,
+            move |connection, _| {
+                let Ok(service) = service.lock() else {
+                    return;
+                };
+                if let Err(err) = service.get_ref().register(&connection) {
+                    eprintln!("Failed to register the D-Bus interface: {}", err);
+                }
+            },
+            |_, _| {},
+            |_, name| eprintln!("Failed to own the D-Bus name {}, is another instance running?", name),
+        )
+    }
+
+    fn method_call(&self, method: &str, parameters: &glib::Variant, invocation: gio::DBusMethodInvocation) {
+        // Only a nested main loop, such as a dialog's, could find them borrowed
+        let (Ok(mut state), Ok(mut history)) = (self.state.try_borrow_mut(), self.history.try_borrow_mut()) else {
+            invocation.return_dbus_error(ERROR_BUSY, "Окно сейчас изменяет задачи, повторите позже");
+            return;
+        };
+        let result = call(&mut state, &mut history, method, parameters);
+        drop((state, history));
+        match result {
+            Ok((reply, changed)) => {
+                if changed {
+                    (self.on_change)();
+                }
+                invocation.return_value(Some(&reply));
+            }
+            Err(err) => invocation.return_dbus_error(error_name(&err), &err.to_string()),
+        }
+    }
+
+    /// Emits a signal for each changed task. Changes made through the history
+    /// are signalled already; this is for the rest, such as reloads from disk.
+    pub fn announce(&self, diffs: &[RowDiff]) {
+        let Some(connection) = self.connection.borrow().clone() else {
+            return;
+        };
+        for (signal, id, date) in diffs.iter().filter_map(signal) {
+            let parameters = (id, date).to_variant();
+            if let Err(err) = connection.emit_signal(None, OBJECT_PATH, INTERFACE, signal, Some(&parameters)) {
+                eprintln!("Failed to emit {}: {}", signal, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Stdio};
+
+    use crate::test_support::{self, run_until};
+
+    /// A bus of our own, so the test neither needs nor disturbs a session bus.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Option<Self> {
+            let mut daemon = std::process::Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            let address = address.trim().to_string();
+            Some(Self { daemon, address })
+        }
+
+        fn connect(&self) -> gio::DBusConnection {
+            let flags = gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION;
+            gio::DBusConnection::for_address_sync(&self.address, flags, None, gio::Cancellable::NONE).unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[test]
+    fn methods_work_on_the_shared_state_and_changes_are_signalled() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        let _data_dir = test_support::data_dir();
+
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let state = Rc::new(RefCell::new(State::new()));
+                let changes = Rc::new(Cell::new(0));
+                let on_change = {
+                    let changes = changes.clone();
+                    move || changes.set(changes.get() + 1)
+                };
+                let history = Rc::new(RefCell::new(History::new()));
+                let service = Service::new(state.clone(), history.clone(), on_change);
+                let server = bus.connect();
+                service.register(&server).unwrap();
+                let server_name = server.unique_name().unwrap().to_string();
+
+                let client = bus.connect();
+                let signals = Rc::new(RefCell::new(Vec::new()));
+                client.signal_subscribe(
+                    Some(&server_name),
+                    Some(INTERFACE),
+                    None,
+                    Some(OBJECT_PATH),
+                    None,
+                    gio::DBusSignalFlags::NONE,
+                    {
+                        let signals = signals.clone();
+                        move |_, _, _, _, signal, parameters| {
+                            let (id, date) = parameters.get::<(u32, String)>().unwrap();
+                            signals.borrow_mut().push((signal.to_string(), id, date));
+                        }
+                    },
+                );
+
+                // The client waits on its own thread while this one serves the call
+                let call = |method: &'static str, parameters: glib::Variant| {
+                    let (client, server_name) = (client.clone(), server_name.clone());
+                    let call = std::thread::spawn(move || {
+                        client.call_sync(
+                            Some(&server_name),
+                            OBJECT_PATH,
+                            INTERFACE,
+                            method,
+                            Some(&parameters),
+                            None,
+                            gio::DBusCallFlags::NONE,
+                            5000,
+                            gio::Cancellable::NONE,
+                        )
+                    });
+                    run_until(&context, || call.is_finished());
+                    call.join().unwrap()
+                };
+
+                let date = "2026-02-19";
+                let added = call("AddTask", (date, "Задача", "").to_variant()).unwrap();
+                assert_eq!(added.get::<(u32,)>(), Some((1,)));
+                call("SetStatus", (1u32, "Готово").to_variant()).unwrap();
+                assert!(state.borrow().get_row(date, 1).unwrap().status.is_done());
+
+                let tasks = call("ListTasks", (date,).to_variant()).unwrap();
+                let (tasks,) = tasks.get::<(Vec<(u32, String, String, String, bool)>,)>().unwrap();
+                let task = (1, "Задача".to_string(), "ready".to_string(), "Готово".to_string(), false);
+                assert_eq!(tasks, vec![task]);
+                let report = call("GenerateReport", (date,).to_variant()).unwrap();
+                assert!(report.get::<(String,)>().unwrap().0.contains("• Задача"));
+
+                let missing = call("SetStatus", (7u32, "ready").to_variant()).unwrap_err();
+                assert!(missing.message().contains(ERROR_NOT_FOUND), "{}", missing);
+                let invalid = call("AddTask", ("19.02.2026", "Задача", "").to_variant()).unwrap_err();
+                assert!(invalid.message().contains(ERROR_INVALID_ARGS), "{}", invalid);
+                call("SetBlocked", (1u32, true).to_variant()).unwrap();
+                call("MoveTask", (1u32, "2026-02-20").to_variant()).unwrap();
+                assert!(state.borrow().get_row("2026-02-20", 1).unwrap().blocked);
+                call("DeleteTask", (1u32,).to_variant()).unwrap();
+                assert!(state.borrow().locate(1).is_none());
+                assert_eq!(changes.get(), 5);
+
+                // Changes made by the window itself are signalled as well
+                history.borrow_mut().undo(&mut state.borrow_mut()).unwrap();
+                run_until(&context, || signals.borrow().len() >= 6);
+                let expected = [
+                    ("TaskAdded", date),
+                    ("TaskChanged", date),
+                    ("TaskChanged", date),
+                    ("TaskChanged", "2026-02-20"),
+                    ("TaskRemoved", "2026-02-20"),
+                    ("TaskAdded", "2026-02-20"),
+                ]
+                .map(|(signal, date)| (signal.to_string(), 1, date.to_string()));
+                assert_eq!(*signals.borrow(), expected);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn windows_tell_which_data_dir_they_work_on() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        let _data_dir = test_support::data_dir();
+        let other_dir = tempfile::tempdir().unwrap();
+
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let state = Rc::new(RefCell::new(State::new()));
+                let service = Service::new(state, Rc::new(RefCell::new(History::new())), || {});
+                let server = bus.connect();
+                service.register(&server).unwrap();
+                let server_name = server.unique_name().unwrap().to_string();
+
+                let client = bus.connect();
+                let ask = |dir: PathBuf| {
+                    let (client, server_name) = (client.clone(), server_name.clone());
+                    let ask = std::thread::spawn(move || serves(&client, &server_name, &dir));
+                    run_until(&context, || ask.is_finished());
+                    ask.join().unwrap()
+                };
+                // A command for another data directory must not change this window's tasks
+                assert!(ask(paths::data_dir().unwrap()));
+                assert!(!ask(other_dir.path().to_path_buf()));
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs::{File, OpenOptions};
+
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::paths;
+
+// The lock lives in a sidecar file: saves replace gtk-reporter.json by rename,
+// which would silently drop a lock held on the state file itself.
+const LOCK_FILE: &str = "gtk-reporter.lock";
+#[cfg(unix)]
+const SOCKET_FILE: &str = "gtk-reporter.sock";
+#[cfg(unix)]
+const ACTIVATE: &str = "activate";
+
+/// Exclusive advisory lock on the data directory, held for the whole run.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+#[derive(Debug)]
+pub enum Instance {
+    /// This process owns the data directory and may write to it.
+    Primary { _lock: InstanceLock },
+    /// Another process already owns the data directory.
+    Secondary,
+}
+
+impl Instance {
+    pub fn acquire() -> Result<Self, ReporterError> {
+        let dir = paths::data_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self::Primary {
+                _lock: InstanceLock { _file: file },
+            }),
+            Err(std::fs::TryLockError::WouldBlock) => Ok(Self::Secondary),
+            Err(std::fs::TryLockError::Error(err)) => Err(err.into()),
+        }
+    }
+
+    pub fn is_primary(&self) -> bool {
+        matches!(self, Self::Primary { .. })
+    }
+}
+
+#[cfg(unix)]
+fn socket_path() -> Result<std::path::PathBuf, ReporterError> {
+    Ok(paths::data_dir()?.join(SOCKET_FILE))
+}
+
+/// Asks the primary instance to bring its window to front.
+/// Returns `false` if nobody answered.
+#[cfg(unix)]
+pub fn activate_primary() -> bool {
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+
+    let Ok(path) = socket_path() else {
+        return false;
+    };
+    match UnixStream::connect(path) {
+        Ok(mut stream) => writeln!(stream, "{}", ACTIVATE).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn activate_primary() -> bool {
+    false
+}
+
+/// Accepts activation requests from later launches on the GTK main loop.
+/// Must only be called by the primary instance.
+#[cfg(unix)]
+pub fn listen<F: Fn() + 'static>(on_activate: F) -> Result<(), ReporterError> {
+    use std::io::BufRead;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::net::UnixListener;
+
+    let path = socket_path()?;
+    // Left over from a crashed run; we hold the lock, so nobody else is listening
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    listener.set_nonblocking(true)?;
+
+    let fd = listener.as_raw_fd();
+    glib::source::unix_fd_add_local(fd, glib::IOCondition::IN, move |_, _| {
+        while let Ok((stream, _)) = listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(std::time::Duration::from_millis(200)));
+            let mut line = String::new();
+            let _ = std::io::BufReader::new(stream).read_line(&mut line);
+            if line.trim() == ACTIVATE {
+                on_activate();
+            }
+        }
+        glib::ControlFlow::Continue
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn listen<F: Fn() + 'static>(_on_activate: F) -> Result<(), ReporterError> {
+    Ok(())
+}
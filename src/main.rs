@@ -1,15 +1,166 @@
-mod error;
-mod state;
+mod api;
+mod cli;
+mod dashboard;
+mod dbus;
+mod instance;
 mod ui;
+mod watch;
 
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::paths;
+use gtk_reporter_core::storage::{self, StorageKind};
+use instance::Instance;
 use ui::App;
 
+const USAGE: &str = "Использование: gtk-reporter [--data-dir <каталог>] [--convert-storage <json|sqlite>]
+       gtk-reporter [--data-dir <каталог>] <команда> [аргументы] [--json]";
+
+#[derive(Default)]
+struct Args {
+    data_dir: Option<PathBuf>,
+    convert_to: Option<StorageKind>,
+    /// A command to run without the window, with its arguments.
+    command: Option<Vec<String>>,
+}
+
+fn utf8(arg: OsString) -> Result<String, String> {
+    arg.into_string()
+        .map_err(|arg| format!("Аргумент не в UTF-8: {}", arg.to_string_lossy()))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let arg_str = arg.to_string_lossy();
+        if let Some(command) = &mut parsed.command
+            && arg_str == "--"
+        {
+            // The rest belongs to the command, even what looks like our options
+            command.push(arg_str.into_owned());
+            for arg in args.by_ref() {
+                command.push(utf8(arg)?);
+            }
+        } else if arg_str == "--data-dir" {
+            let value = args.next().ok_or("--data-dir: не указан каталог")?;
+            parsed.data_dir = Some(PathBuf::from(value));
+        } else if let Some(value) = arg_str.strip_prefix("--data-dir=") {
+            parsed.data_dir = Some(PathBuf::from(value));
+        } else if arg_str == "--convert-storage" {
+            let value = args.next().ok_or("--convert-storage: не указан формат")?;
+            let kind = StorageKind::parse(&value.to_string_lossy())
+                .ok_or("--convert-storage: ожидается json или sqlite")?;
+            parsed.convert_to = Some(kind);
+        } else if arg_str == "-h" || arg_str == "--help" {
+            println!("{}\n\n{}", USAGE, cli::USAGE);
+            std::process::exit(0);
+        } else if parsed.command.is_none() && cli::COMMANDS.contains(&&*arg_str) {
+            parsed.command = Some(vec![arg_str.into_owned()]);
+        } else if let Some(command) = &mut parsed.command {
+            command.push(utf8(arg)?);
+        } else {
+            return Err(format!("Неизвестный аргумент: {}", arg_str));
+        }
+    }
+    Ok(parsed)
+}
+
+fn convert_storage(to: StorageKind) -> Result<PathBuf, ReporterError> {
+    // A running window would keep writing to the old file
+    let instance = Instance::acquire()?;
+    if !instance.is_primary() {
+        return Err(ReporterError::AlreadyRunning);
+    }
+    storage::convert(to)
+}
+
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(command) = args.command {
+        if let Err(err) = paths::init(args.data_dir) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        std::process::exit(cli::run(command));
+    }
+
+    if let Some(to) = args.convert_to {
+        let result = paths::init(args.data_dir).and_then(|_| convert_storage(to));
+        match result {
+            Ok(old) => println!("Данные переведены в формат {}, прежний файл: {}", to.to_str(), old.display()),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     if gtk::init().is_err() {
         eprintln!("Failed to init GTK.");
         return;
     }
 
-    let app = App::new();
+    if let Err(err) = paths::init(args.data_dir) {
+        ui::show_error(&err.to_string());
+        return;
+    }
+
+    let instance = match Instance::acquire() {
+        Ok(instance) => instance,
+        Err(err) => {
+            ui::show_error(&err.to_string());
+            return;
+        }
+    };
+    if !instance.is_primary() && instance::activate_primary() {
+        return;
+    }
+
+    let app = App::new(instance);
     app.run();
 }
+
+#[cfg(test)]
+mod test_support {
+    use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+    use std::time::{Duration, Instant};
+
+    use gtk_reporter_core::{paths, status};
+
+    /// Points the data directory at a temporary one and keeps other tests that
+    /// save out of it until the guard is dropped. The directory can only be set
+    /// once per process, so they all share it.
+    pub fn data_dir() -> MutexGuard<'static, ()> {
+        static INIT: Once = Once::new();
+        static LOCK: Mutex<()> = Mutex::new(());
+        INIT.call_once(|| paths::init(Some(tempfile::tempdir().unwrap().keep())).unwrap());
+        statuses();
+        LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sets up the built-in statuses, as the app does without a config.
+    pub fn statuses() {
+        status::init(Vec::new()).unwrap();
+    }
+
+    /// Runs `context` until `done` or a few seconds have passed.
+    pub fn run_until(context: &glib::MainContext, done: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() && Instant::now() < deadline {
+            if !context.iteration(false) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
@@ -1,14 +1,39 @@
 use gtk::prelude::*;
 use gtk::{Box as GtkBox, Button, Entry, Label, Orientation, Stack, TextView, Window, Align, ScrolledWindow};
 use pango::WrapMode;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
-
-use crate::state::{Row, State, Status};
+use std::time::Duration;
+
+use gtk_reporter_core::backup::{self, Snapshot};
+use gtk_reporter_core::config::Config;
+use gtk_reporter_core::error::ReporterError;
+use gtk_reporter_core::history::{Command, History};
+use gtk_reporter_core::journal;
+use gtk_reporter_core::paths;
+use gtk_reporter_core::report;
+use gtk_reporter_core::search;
+use gtk_reporter_core::state::{Row, RowChange, RowDiff, State, TaskOrder, validate_date};
+use gtk_reporter_core::stats::{self, Stats};
+use gtk_reporter_core::status::{self, Status};
+use gtk_reporter_core::template::{self, Template};
+
+use crate::api::Api;
+use crate::dashboard::{self, Chart};
+use crate::dbus;
+use crate::instance::{self, Instance};
+use crate::watch;
 
 const SCREEN_MAIN: &str = "main";
 const SCREEN_TASKS: &str = "tasks";
 const SCREEN_EDIT: &str = "edit";
+const SCREEN_BACKUPS: &str = "backups";
+const SCREEN_STATS: &str = "stats";
+const SCREEN_SEARCH: &str = "search";
+
+const TOAST_TIMEOUT: Duration = Duration::from_secs(6);
+// More results than this only slow the list down
+const SEARCH_LIMIT: usize = 200;
 
 pub struct App {
     pub window: Window,
@@ -16,6 +41,13 @@ pub struct App {
     pub state: Rc<RefCell<State>>,
     pub edit_context: Rc<RefCell<Option<EditContext>>>,
     pub edit_widgets: Rc<RefCell<Option<EditWidgets>>>,
+    pub instance: Instance,
+    pub file_monitor: RefCell<Option<gtk::gio::FileMonitor>>,
+    pub api: RefCell<Option<Api>>,
+    pub error_bar: ErrorBar,
+    pub history: Rc<RefCell<History>>,
+    pub toast: Toast,
+    pub config: Rc<Config>,
 }
 
 pub struct EditContext {
@@ -27,18 +59,265 @@ pub struct EditWidgets {
     pub header: Label,
     pub text_buffer: gtk::TextBuffer,
     pub status_combo: gtk::ComboBoxText,
+    pub blocked_check: gtk::CheckButton,
     pub date_entry: Entry,
     pub delete_btn: Button,
+    pub history: gtk::Expander,
+    pub history_label: Label,
+}
+
+/// Non-modal strip above the screens for errors that shouldn't interrupt the user.
+#[derive(Clone)]
+pub struct ErrorBar {
+    bar: gtk::InfoBar,
+    label: Label,
+}
+
+impl ErrorBar {
+    fn new() -> Self {
+        let bar = gtk::InfoBar::new();
+        bar.set_message_type(gtk::MessageType::Error);
+        bar.set_show_close_button(true);
+        bar.connect_response(|bar, _| bar.hide());
+        let label = Label::new(None);
+        label.set_line_wrap(true);
+        label.set_xalign(0.0);
+        label.show();
+        bar.content_area().add(&label);
+        // Stays hidden through show_all until there is something to report
+        bar.set_no_show_all(true);
+        Self { bar, label }
+    }
+
+    pub fn show(&self, message: &str) {
+        self.label.set_text(message);
+        self.bar.show();
+    }
+
+    pub fn hide(&self) {
+        self.bar.hide();
+    }
+}
+
+/// In-app notification over the top of the screens that offers to undo the last action.
+#[derive(Clone)]
+pub struct Toast {
+    revealer: gtk::Revealer,
+    label: Label,
+    undo_btn: Button,
+    timeout: Rc<RefCell<Option<glib::SourceId>>>,
+    /// The history version the shown action left.
+    step: Rc<Cell<u64>>,
+}
+
+impl Toast {
+    fn new() -> Self {
+        let revealer = gtk::Revealer::new();
+        revealer.set_halign(Align::Center);
+        revealer.set_valign(Align::Start);
+
+        let hbox = GtkBox::new(Orientation::Horizontal, 10);
+        hbox.style_context().add_class("app-notification");
+        let label = Label::new(None);
+        let undo_btn = Button::with_label("Отменить");
+        hbox.pack_start(&label, false, false, 0);
+        hbox.pack_start(&undo_btn, false, false, 0);
+        revealer.add(&hbox);
+
+        Self {
+            revealer,
+            label,
+            undo_btn,
+            timeout: Rc::new(RefCell::new(None)),
+            step: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// `on_undo` gets the history version of the action the toast is about.
+    pub fn connect_undo<F: Fn(u64) + 'static>(&self, on_undo: F) {
+        let toast = self.clone();
+        self.undo_btn.connect_clicked(move |_| {
+            toast.hide();
+            on_undo(toast.step.get());
+        });
+    }
+
+    /// Offers to undo the action that left history at `step`.
+    pub fn show(&self, message: &str, step: u64) {
+        self.step.set(step);
+        self.label.set_text(message);
+        self.revealer.set_reveal_child(true);
+        let toast = self.clone();
+        let source = glib::timeout_add_local_once(TOAST_TIMEOUT, move || {
+            toast.timeout.borrow_mut().take();
+            toast.revealer.set_reveal_child(false);
+        });
+        if let Some(previous) = self.timeout.borrow_mut().replace(source) {
+            previous.remove();
+        }
+    }
+
+    pub fn hide(&self) {
+        if let Some(source) = self.timeout.borrow_mut().take() {
+            source.remove();
+        }
+        self.revealer.set_reveal_child(false);
+    }
 }
 
 struct Screens {
     date_store: gtk::ListStore,
     task_store: gtk::ListStore,
+    task_order: gtk::ComboBoxText,
     date_label: Label,
+    backup_store: gtk::ListStore,
+    templates: Vec<Template>,
+    report_template: gtk::ComboBoxText,
+    report_preview: gtk::TextBuffer,
+    stats: Rc<RefCell<Stats>>,
+    stats_summary: Label,
+    stats_grid: gtk::Grid,
+    search_entry: gtk::SearchEntry,
+    search_status: gtk::ComboBoxText,
+    search_from: Entry,
+    search_to: Entry,
+    search_store: gtk::ListStore,
+    search_summary: Label,
+    /// Told about changes that bypass the history, to signal them on D-Bus.
+    service: Option<Rc<dbus::Service>>,
+}
+
+impl Screens {
+    fn refresh_dates(&self, state: &State) {
+        self.date_store.clear();
+        for date in &state.get_all_dates() {
+            let iter = self.date_store.append();
+            self.date_store.set_value(&iter, 0, &date.to_value());
+        }
+    }
+
+    fn refresh_tasks(&self, state: &State) {
+        self.task_store.clear();
+        let order = self
+            .task_order
+            .active_id()
+            .and_then(|id| TaskOrder::parse(&id))
+            .unwrap_or(TaskOrder::Manual);
+        for row in state.get_rows_sorted(&state.cur_date, order) {
+            let when = order.timestamp(&row).map(relative_time).unwrap_or_default();
+            // Unset rather than empty, GTK can't parse an empty colour
+            let colour = Some(row.status.colour()).filter(|colour| !colour.is_empty());
+            let iter = self.task_store.append();
+            self.task_store.set(&iter, &[
+                (0, &row.id),
+                (1, &row.text),
+                (2, &status_text(&row)),
+                (3, &when),
+                (4, &colour),
+            ]);
+        }
+        self.date_label.set_markup(&format!("<span size='medium'>Дата: {}</span>", state.cur_date));
+        self.refresh_preview(state);
+    }
+
+    /// Rereads the templates directory, keeping the picked template if it's still there.
+    fn refresh_templates(&mut self) -> Result<(), ReporterError> {
+        let active = self.report_template.active_id();
+        self.templates = template::load_all()?;
+        self.report_template.remove_all();
+        for template in &self.templates {
+            self.report_template.append(Some(&template.name), &template.name);
+        }
+        let picked = active.is_some_and(|name| self.report_template.set_active_id(Some(&name)));
+        if !picked {
+            self.report_template.set_active_id(Some(template::STANDARD));
+        }
+        Ok(())
+    }
+
+    fn selected_template(&self) -> Template {
+        let name = self.report_template.active_id();
+        self.templates
+            .iter()
+            .find(|template| Some(template.name.as_str()) == name.as_deref())
+            .cloned()
+            .unwrap_or_else(Template::standard)
+    }
+
+    fn render_report(&self, state: &State) -> Result<String, ReporterError> {
+        self.selected_template().render(&report::day_context(state, &state.cur_date))
+    }
+
+    fn refresh_preview(&self, state: &State) {
+        let preview = self.render_report(state).unwrap_or_else(|err| err.to_string());
+        self.report_preview.set_text(&preview);
+    }
+
+    fn refresh_search(&self, state: &State) {
+        self.search_store.clear();
+        let date_filter = |entry: &Entry| -> Result<Option<String>, ReporterError> {
+            let date = entry.text().trim().to_string();
+            if date.is_empty() {
+                return Ok(None);
+            }
+            validate_date(&date)?;
+            Ok(Some(date))
+        };
+        let (from, to) = match (date_filter(&self.search_from), date_filter(&self.search_to)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(err), _) | (_, Err(err)) => {
+                self.search_summary.set_text(&err.to_string());
+                return;
+            }
+        };
+        let query = search::Query {
+            text: self.search_entry.text().to_string(),
+            status: self.search_status.active_id().map(String::from).filter(|id| !id.is_empty()),
+            from,
+            to,
+        };
+
+        let (hits, total) = search::search(state, &query, SEARCH_LIMIT);
+        for hit in &hits {
+            let colour = Some(hit.row.status.colour()).filter(|colour| !colour.is_empty());
+            let iter = self.search_store.append();
+            self.search_store.set(&iter, &[
+                (0, &hit.row.id),
+                (1, &hit.date),
+                (2, &status_text(&hit.row)),
+                (3, &snippet_markup(&hit.snippet)),
+                (4, &colour),
+            ]);
+        }
+        let summary = if total == 0 {
+            "Ничего не найдено".to_string()
+        } else if total > hits.len() {
+            format!("Найдено задач: {}, показаны первые {}", total, hits.len())
+        } else {
+            format!("Найдено задач: {}", total)
+        };
+        self.search_summary.set_text(&summary);
+    }
+
+    fn refresh_stats(&self, state: &State, holidays: &[chrono::NaiveDate]) {
+        let stats = stats::collect(state, chrono::Local::now().date_naive(), holidays);
+        let lead_time = stats
+            .lead_time_overall
+            .map_or("нет данных".to_string(), dashboard::format_duration);
+        self.stats_summary.set_text(&format!(
+            "Всего задач: {}   Среднее время до готовности: {}   Серия: {} дн. (рекорд {})",
+            state.task_count(),
+            lead_time,
+            stats.current_streak,
+            stats.longest_streak
+        ));
+        *self.stats.borrow_mut() = stats;
+        self.stats_grid.queue_draw();
+    }
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(instance: Instance) -> Self {
         let window = Window::new(gtk::WindowType::Toplevel);
         window.set_title("Ежедневник");
         window.set_default_size(700, 550);
@@ -47,15 +326,44 @@ impl App {
         let stack = Stack::new();
         stack.set_transition_type(gtk::StackTransitionType::SlideLeftRight);
 
-        // Load state from file or create new
-        let state = Rc::new(RefCell::new(
-            State::load().unwrap_or_else(|_| State::new())
-        ));
+        let config = Config::load().unwrap_or_else(|err| {
+            show_error(&format!("Не удалось прочитать настройки, используются настройки по умолчанию: {}", err));
+            Config::default()
+        });
+        if let Err(err) = status::init(config.statuses.clone()) {
+            show_error(&err.to_string());
+        }
+
+        // Load state from file, create new only if there is no file yet
+        let state = load_state(instance.is_primary());
+        if instance.is_primary() {
+            let window = window.clone();
+            if let Err(err) = instance::listen(move || window.present()) {
+                eprintln!("Failed to listen for other instances: {}", err);
+            }
+        }
+        if state.read_only {
+            window.set_title("Ежедневник (только чтение)");
+        }
+        let state = Rc::new(RefCell::new(state));
 
         let edit_context = Rc::new(RefCell::new(None));
         let edit_widgets = Rc::new(RefCell::new(None));
 
-        let app = Self { window, stack, state, edit_context, edit_widgets };
+        let app = Self {
+            window,
+            stack,
+            state,
+            edit_context,
+            edit_widgets,
+            instance,
+            file_monitor: RefCell::new(None),
+            api: RefCell::new(None),
+            error_bar: ErrorBar::new(),
+            history: Rc::new(RefCell::new(History::new())),
+            toast: Toast::new(),
+            config: Rc::new(config),
+        };
         app.setup_ui();
         app
     }
@@ -63,72 +371,49 @@ impl App {
     fn setup_ui(&self) {
         let screens = Rc::new(RefCell::new(self.create_screens()));
 
+        if self.config.carry_over.on_startup {
+            self.carry_over_on_startup();
+        }
+
         // Initial populate
         {
             let s = self.state.borrow();
-            let dates = s.get_all_dates();
-            let date_store = &screens.borrow().date_store;
-            date_store.clear();
-            for date in &dates {
-                let iter = date_store.append();
-                date_store.set_value(&iter, 0, &date.to_value());
-            }
-            
-            let rows = s.get_rows_for_date(&s.cur_date);
-            let task_store = &screens.borrow().task_store;
-            task_store.clear();
-            for row in rows {
-                let iter = task_store.append();
-                task_store.set(&iter, &[
-                    (0, &row.id),
-                    (1, &row.text),
-                    (2, &row.status.to_str()),
-                ]);
-            }
-            screens.borrow().date_label.set_markup(&format!("<span size='medium'>Дата: {}</span>", s.cur_date));
+            screens.borrow().refresh_dates(&s);
+            screens.borrow().refresh_tasks(&s);
         }
 
         let main_screen = self.create_main_screen(&screens);
         let tasks_screen = self.create_tasks_screen(&screens);
         let edit_screen = self.create_edit_screen(&screens);
+        let backups_screen = self.create_backups_screen(&screens);
+        let stats_screen = self.create_stats_screen(&screens);
+        let search_screen = self.create_search_screen(&screens);
 
         self.stack.add_titled(&main_screen, SCREEN_MAIN, "Главная");
         self.stack.add_titled(&tasks_screen, SCREEN_TASKS, "Задачи");
         self.stack.add_titled(&edit_screen, SCREEN_EDIT, "Редактирование");
+        self.stack.add_titled(&backups_screen, SCREEN_BACKUPS, "Резервные копии");
+        self.stack.add_titled(&stats_screen, SCREEN_STATS, "Статистика");
+        self.stack.add_titled(&search_screen, SCREEN_SEARCH, "Поиск");
 
         // Refresh lists when switching screens
         let state_clone = self.state.clone();
         let screens_clone = screens.clone();
         let edit_context = self.edit_context.clone();
         let edit_widgets = self.edit_widgets.clone();
+        let error_bar = self.error_bar.clone();
+        let config = self.config.clone();
         self.stack.connect_visible_child_notify(move |stack| {
             let visible = stack.visible_child_name().unwrap_or_default();
+            // Errors belong to the screen they happened on
+            error_bar.hide();
             
             if visible == SCREEN_MAIN {
                 // Refresh date list
-                let dates = state_clone.borrow().get_all_dates();
-                let date_store = &screens_clone.borrow().date_store;
-                date_store.clear();
-                for date in &dates {
-                    let iter = date_store.append();
-                    date_store.set_value(&iter, 0, &date.to_value());
-                }
+                screens_clone.borrow().refresh_dates(&state_clone.borrow());
             } else if visible == SCREEN_TASKS {
                 // Refresh task list
-                let s = state_clone.borrow();
-                let rows = s.get_rows_for_date(&s.cur_date);
-                let task_store = &screens_clone.borrow().task_store;
-                let date_label = &screens_clone.borrow().date_label;
-                task_store.clear();
-                for row in rows {
-                    let iter = task_store.append();
-                    task_store.set(&iter, &[
-                        (0, &row.id),
-                        (1, &row.text),
-                        (2, &row.status.to_str()),
-                    ]);
-                }
-                date_label.set_markup(&format!("<span size='medium'>Дата: {}</span>", s.cur_date));
+                screens_clone.borrow().refresh_tasks(&state_clone.borrow());
             } else if visible == SCREEN_EDIT {
                 // Handle edit screen - load task data if editing
                 let mut ctx = edit_context.borrow_mut();
@@ -138,36 +423,182 @@ impl App {
                     ctx.date = s.cur_date.clone();
                     
                     // Populate widgets if we have a task to edit
-                    if let Some(task_id) = ctx.task_id {
-                        if let Some(row) = s.get_row(&ctx.date, task_id) {
-                            if let Some(widgets) = edit_widgets.borrow().as_ref() {
-                                widgets.header.set_markup("<span size='large' weight='bold'>✏️ Редактирование задачи</span>");
-                                widgets.text_buffer.set_text(&row.text);
-                                match row.status {
-                                    Status::Working => widgets.status_combo.set_active(Some(0)),
-                                    Status::Testing => widgets.status_combo.set_active(Some(1)),
-                                    Status::Ready => widgets.status_combo.set_active(Some(2)),
-                                    Status::Open => widgets.status_combo.set_active(Some(3)),
-                                }
-                                widgets.date_entry.set_text(&ctx.date);
-                                widgets.delete_btn.set_visible(true);
-                            }
-                        }
+                    if let Some(task_id) = ctx.task_id
+                        && let Some(row) = s.get_row(&ctx.date, task_id)
+                        && let Some(widgets) = edit_widgets.borrow().as_ref()
+                    {
+                        widgets.header.set_markup("<span size='large' weight='bold'>✏️ Редактирование задачи</span>");
+                        widgets.text_buffer.set_text(&row.text);
+                        fill_status_combo(&widgets.status_combo, &row.status);
+                        widgets.blocked_check.set_active(row.blocked);
+                        widgets.date_entry.set_text(&ctx.date);
+                        widgets.delete_btn.set_visible(true);
+                        widgets.history_label.set_text(&task_timeline(&s.carry_chain(task_id)));
+                        widgets.history.set_visible(true);
                     }
                 } else {
                     // New task - clear widgets
                     if let Some(widgets) = edit_widgets.borrow().as_ref() {
                         widgets.header.set_markup("<span size='large' weight='bold'>✏️ Новая задача</span>");
                         widgets.text_buffer.set_text("");
-                        widgets.status_combo.set_active(Some(0));
+                        fill_status_combo(&widgets.status_combo, &Status::initial());
+                        widgets.blocked_check.set_active(false);
                         widgets.date_entry.set_text(&chrono::Local::now().format("%Y-%m-%d").to_string());
                         widgets.delete_btn.set_visible(false);
+                        widgets.history.set_visible(false);
                     }
                 }
+            } else if visible == SCREEN_BACKUPS {
+                // Refresh snapshot list
+                fill_backup_store(&screens_clone.borrow().backup_store);
+            } else if visible == SCREEN_STATS {
+                screens_clone.borrow().refresh_stats(&state_clone.borrow(), &config.holidays);
+            } else if visible == SCREEN_SEARCH {
+                screens_clone.borrow().refresh_search(&state_clone.borrow());
+            }
+        });
+
+        let vbox = GtkBox::new(Orientation::Vertical, 0);
+        if self.state.borrow().read_only {
+            let message = if self.instance.is_primary() {
+                "Данные открыты только для чтения — изменения не будут сохранены."
+            } else {
+                "Ежедневник уже запущен в другом окне — здесь данные открыты только для чтения."
+            };
+            vbox.pack_start(&read_only_banner(message), false, false, 0);
+        }
+        vbox.pack_start(&self.error_bar.bar, false, false, 0);
+        let overlay = gtk::Overlay::new();
+        overlay.add(&self.stack);
+        overlay.add_overlay(&self.toast.revealer);
+        vbox.pack_start(&overlay, true, true, 0);
+        self.window.add(&vbox);
+
+        // Undo and redo go through the same path for the shortcuts and the toast
+        let step_history = Rc::new({
+            let state = self.state.clone();
+            let history = self.history.clone();
+            let stack = self.stack.clone();
+            let screens = screens.clone();
+            let error_bar = self.error_bar.clone();
+            move |redo: bool| {
+                let mut s = state.borrow_mut();
+                let result = if redo {
+                    history.borrow_mut().redo(&mut s)
+                } else {
+                    history.borrow_mut().undo(&mut s)
+                };
+                let mut unjournaled = None;
+                match result {
+                    Ok(true) => {
+                        screens.borrow().refresh_dates(&s);
+                        screens.borrow().refresh_tasks(&s);
+                    }
+                    Ok(false) => {}
+                    Err(err @ ReporterError::Journal(_)) => {
+                        screens.borrow().refresh_dates(&s);
+                        screens.borrow().refresh_tasks(&s);
+                        unjournaled = Some(err);
+                    }
+                    Err(err) => {
+                        let action = if redo { "повторить" } else { "отменить" };
+                        error_bar.show(&format!("Не удалось {} действие: {}", action, err));
+                    }
+                }
+                drop(s);
+                if let Some(err) = unjournaled {
+                    show_error(&err.to_string());
+                }
+                // The task on the form may have just changed or disappeared
+                if stack.visible_child_name().as_deref() == Some(SCREEN_EDIT) {
+                    stack.set_visible_child_name(SCREEN_TASKS);
+                }
+            }
+        });
+        self.toast.connect_undo({
+            let history = self.history.clone();
+            let step_history = step_history.clone();
+            move |step| {
+                // Something newer would be undone instead
+                if history.borrow().version() == step {
+                    step_history(false);
+                }
+            }
+        });
+        // Any later change makes the toast's undo mean something else
+        self.history.borrow_mut().add_listener({
+            let toast = self.toast.clone();
+            move |_| toast.hide()
+        });
+        self.window.connect_key_press_event({
+            let stack = self.stack.clone();
+            let search_entry = screens.borrow().search_entry.clone();
+            move |_, event| {
+                let ctrl = event.state().contains(gtk::gdk::ModifierType::CONTROL_MASK);
+                let shift = event.state().contains(gtk::gdk::ModifierType::SHIFT_MASK);
+                // Text fields on the edit form keep their own shortcuts
+                let editing = stack.visible_child_name().as_deref() == Some(SCREEN_EDIT);
+                if !ctrl || editing {
+                    return glib::Propagation::Proceed;
+                }
+                let key = event.keyval().to_lower();
+                if key == gtk::gdk::keys::constants::z {
+                    step_history(shift);
+                } else if key == gtk::gdk::keys::constants::f {
+                    stack.set_visible_child_name(SCREEN_SEARCH);
+                    search_entry.grab_focus();
+                } else {
+                    return glib::Propagation::Proceed;
+                }
+                glib::Propagation::Stop
             }
         });
 
-        self.window.add(&self.stack);
+        // Pick up edits made to the file by hand or by a sync tool
+        let on_reload = {
+            let state = self.state.clone();
+            let screens = screens.clone();
+            let history = self.history.clone();
+            move |diffs: &[RowDiff]| {
+                // Steps recorded against the old data can't be replayed safely
+                history.borrow_mut().clear();
+                let s = state.borrow();
+                let screens = screens.borrow();
+                if let Some(service) = &screens.service {
+                    service.announce(diffs);
+                }
+                screens.refresh_dates(&s);
+                screens.refresh_tasks(&s);
+            }
+        };
+        match watch::watch_state_file(self.state.clone(), on_reload) {
+            Ok(monitor) => *self.file_monitor.borrow_mut() = Some(monitor),
+            Err(err) => self.error_bar.show(&format!("Изменения файла с задачами извне не будут видны: {}", err)),
+        }
+
+        // Scripts, desktop widgets and browser extensions change tasks through
+        // this window, over D-Bus and, if enabled, HTTP
+        if self.instance.is_primary() {
+            let on_change = {
+                let state = self.state.clone();
+                let screens = screens.clone();
+                move || {
+                    let s = state.borrow();
+                    screens.borrow().refresh_dates(&s);
+                    screens.borrow().refresh_tasks(&s);
+                }
+            };
+            let service = dbus::Service::new(self.state.clone(), self.history.clone(), on_change.clone());
+            service.own_name();
+            screens.borrow_mut().service = Some(service);
+
+            if self.config.api.enabled {
+                match Api::start(&self.config.api, self.state.clone(), self.history.clone(), on_change) {
+                    Ok(api) => *self.api.borrow_mut() = Some(api),
+                    Err(err) => self.error_bar.show(&format!("Не удалось запустить HTTP API: {}", err)),
+                }
+            }
+        }
 
         self.window.connect_delete_event(|_, _| {
             gtk::main_quit();
@@ -181,14 +612,68 @@ impl App {
             gtk::glib::Type::U32,
             gtk::glib::Type::STRING,
             gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
         ]);
+        let task_order = gtk::ComboBoxText::new();
+        for (order, title) in [
+            (TaskOrder::Manual, "По порядку добавления"),
+            (TaskOrder::Created, "Сначала новые"),
+            (TaskOrder::Updated, "Недавно изменённые"),
+            (TaskOrder::StatusChanged, "Дольше всего в статусе"),
+        ] {
+            task_order.append(Some(order.to_str()), title);
+        }
+        task_order.set_active_id(Some(TaskOrder::Manual.to_str()));
         let date_label = Label::new(None);
+        let backup_store = gtk::ListStore::new(&[
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::U32,
+            gtk::glib::Type::STRING,
+        ]);
+        let report_template = gtk::ComboBoxText::new();
+        report_template.set_tooltip_text(Some("Шаблон отчёта"));
+        let report_preview = gtk::TextBuffer::new(None::<&gtk::TextTagTable>);
+        let search_status = gtk::ComboBoxText::new();
+        search_status.append(Some(""), "Все статусы");
+        for status in Status::all() {
+            search_status.append(Some(status.id()), status.label());
+        }
+        search_status.set_active_id(Some(""));
+        let search_store = gtk::ListStore::new(&[
+            gtk::glib::Type::U32,
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
+            gtk::glib::Type::STRING,
+        ]);
 
-        Screens {
+        let mut screens = Screens {
             date_store,
             task_store,
+            task_order,
             date_label,
+            backup_store,
+            templates: Vec::new(),
+            report_template,
+            report_preview,
+            stats: Rc::new(RefCell::new(Stats::default())),
+            stats_summary: Label::new(None),
+            stats_grid: gtk::Grid::new(),
+            search_entry: gtk::SearchEntry::new(),
+            search_status,
+            search_from: Entry::new(),
+            search_to: Entry::new(),
+            search_store,
+            search_summary: Label::new(None),
+            service: None,
+        };
+        if let Err(err) = screens.refresh_templates() {
+            self.error_bar.show(&format!("Не удалось загрузить шаблоны отчётов: {}", err));
         }
+        screens
     }
 
     fn create_main_screen(&self, screens: &Rc<RefCell<Screens>>) -> gtk::Widget {
@@ -239,10 +724,72 @@ impl App {
             }
         });
 
+        let standup_btn = Button::with_label("🗣 Стендап");
+        standup_btn.set_tooltip_text(Some("Скопировать отчёт «Вчера / Сегодня / Блокеры» за сегодня"));
+        standup_btn.connect_clicked({
+            let state = self.state.clone();
+            let config = self.config.clone();
+            move |_| {
+                let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+                let ctx = match report::standup_context(&state.borrow(), &today, &config.holidays) {
+                    Ok(ctx) => ctx,
+                    Err(err) => {
+                        show_error(&err.to_string());
+                        return;
+                    }
+                };
+                copy_report(report::standup_text(&ctx), Some(report::standup_html(&ctx)));
+                show_info("Отчёт для стендапа скопирован в буфер обмена!");
+            }
+        });
+
+        let range_btn = Button::with_label("📊 Отчёт за период");
+        range_btn.connect_clicked({
+            let state = self.state.clone();
+            let screens = screens.clone();
+            let config = self.config.clone();
+            move |_| {
+                let templates = screens.borrow().templates.clone();
+                range_report_dialog(state.borrow().clone(), templates, &config);
+            }
+        });
+
+        let stats_btn = Button::with_label("📈 Статистика");
+        stats_btn.connect_clicked({
+            let stack = self.stack.clone();
+            move |_| {
+                stack.set_visible_child_name(SCREEN_STATS);
+            }
+        });
+
+        let search_btn = Button::with_label("🔍 Поиск");
+        search_btn.set_tooltip_text(Some("Найти задачу за любой день (Ctrl+F)"));
+        search_btn.connect_clicked({
+            let stack = self.stack.clone();
+            let search_entry = screens.borrow().search_entry.clone();
+            move |_| {
+                stack.set_visible_child_name(SCREEN_SEARCH);
+                search_entry.grab_focus();
+            }
+        });
+
+        let backups_btn = Button::with_label("🗄️ Резервные копии");
+        backups_btn.connect_clicked({
+            let stack = self.stack.clone();
+            move |_| {
+                stack.set_visible_child_name(SCREEN_BACKUPS);
+            }
+        });
+
         let btn_box = GtkBox::new(Orientation::Horizontal, 10);
         btn_box.set_halign(Align::Center);
         btn_box.pack_start(&today_btn, false, false, 5);
         btn_box.pack_start(&new_task_btn, false, false, 5);
+        btn_box.pack_start(&standup_btn, false, false, 5);
+        btn_box.pack_start(&range_btn, false, false, 5);
+        btn_box.pack_start(&stats_btn, false, false, 5);
+        btn_box.pack_start(&search_btn, false, false, 5);
+        btn_box.pack_start(&backups_btn, false, false, 5);
         vbox.pack_start(&btn_box, false, false, 5);
 
         // Double-click to select date
@@ -278,6 +825,18 @@ impl App {
         date_label.set_markup("<span size='medium'>Дата: </span>");
         vbox.pack_start(date_label, false, false, 5);
 
+        let order_box = GtkBox::new(Orientation::Horizontal, 10);
+        order_box.set_halign(Align::End);
+        order_box.pack_start(&Label::new(Some("Сортировка:")), false, false, 0);
+        let task_order = screens.borrow().task_order.clone();
+        order_box.pack_start(&task_order, false, false, 0);
+        task_order.connect_changed({
+            let state = self.state.clone();
+            let screens = screens.clone();
+            move |_| screens.borrow().refresh_tasks(&state.borrow())
+        });
+        vbox.pack_start(&order_box, false, false, 0);
+
         // Task list using TreeView
         let scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
         scrolled.set_vexpand(true);
@@ -299,8 +858,17 @@ impl App {
         let status_column = gtk::TreeViewColumn::new();
         gtk::prelude::CellLayoutExt::pack_start(&status_column, &status_renderer, true);
         gtk::prelude::CellLayoutExt::add_attribute(&status_column, &status_renderer, "text", 2);
+        gtk::prelude::CellLayoutExt::add_attribute(&status_column, &status_renderer, "foreground", 4);
         tree_view.append_column(&status_column);
 
+        // Relative time of the timestamp the list is sorted by
+        let time_renderer = gtk::CellRendererText::new();
+        time_renderer.set_property("foreground", "gray");
+        let time_column = gtk::TreeViewColumn::new();
+        gtk::prelude::CellLayoutExt::pack_start(&time_column, &time_renderer, true);
+        gtk::prelude::CellLayoutExt::add_attribute(&time_column, &time_renderer, "text", 3);
+        tree_view.append_column(&time_column);
+
         // Edit button column
         let btn_renderer = gtk::CellRendererText::new();
         btn_renderer.set_property("text", "✏️");
@@ -309,7 +877,23 @@ impl App {
         tree_view.append_column(&btn_column);
 
         scrolled.add(&tree_view);
-        vbox.pack_start(&scrolled, true, true, 5);
+
+        // Report as it will be copied, with the picked template
+        let preview_view = TextView::with_buffer(&screens.borrow().report_preview);
+        preview_view.set_editable(false);
+        preview_view.set_cursor_visible(false);
+        preview_view.set_monospace(true);
+        preview_view.set_wrap_mode(gtk::WrapMode::Word);
+        let preview_scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        preview_scrolled.add(&preview_view);
+        let preview_frame = gtk::Frame::new(Some("Предпросмотр отчёта"));
+        preview_frame.add(&preview_scrolled);
+
+        let paned = gtk::Paned::new(Orientation::Horizontal);
+        paned.set_vexpand(true);
+        paned.pack1(&scrolled, true, false);
+        paned.pack2(&preview_frame, true, true);
+        vbox.pack_start(&paned, true, true, 5);
 
         // Buttons
         let btn_box = GtkBox::new(Orientation::Horizontal, 10);
@@ -331,16 +915,108 @@ impl App {
             }
         });
 
+        let carry_btn = Button::with_label("↪️ Перенести незавершённые");
+        carry_btn.set_tooltip_text(Some("Перенести задачи без статуса «Готово» на сегодня"));
+        carry_btn.connect_clicked({
+            let state = self.state.clone();
+            let history = self.history.clone();
+            let screens = screens.clone();
+            let toast = self.toast.clone();
+            let error_bar = self.error_bar.clone();
+            let mode = self.config.carry_over.mode;
+            move |_| {
+                let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+                // From the day on screen, or from the latest earlier day when looking at today
+                let from = {
+                    let s = state.borrow();
+                    if s.cur_date < today {
+                        Some(s.cur_date.clone())
+                    } else {
+                        s.previous_date(&today)
+                    }
+                };
+                let Some(from) = from else {
+                    show_info("Нет задач за прошлые дни");
+                    return;
+                };
+
+                let command = Command::CarryOver {
+                    from: from.clone(),
+                    to: today.clone(),
+                    mode,
+                };
+                let result = history.borrow_mut().execute(&mut state.borrow_mut(), command);
+                match result {
+                    Ok(rows) if rows.is_empty() => show_info(&format!("За {} нет незавершённых задач", from)),
+                    Ok(rows) => {
+                        let mut s = state.borrow_mut();
+                        s.cur_date = today;
+                        screens.borrow().refresh_tasks(&s);
+                        drop(s);
+                        error_bar.hide();
+                        toast.show(&format!("Перенесено задач с {}: {}", from, rows.len()), history.borrow().version());
+                    }
+                    Err(err @ ReporterError::Journal(_)) => {
+                        let mut s = state.borrow_mut();
+                        s.cur_date = today;
+                        screens.borrow().refresh_tasks(&s);
+                        drop(s);
+                        show_error(&err.to_string());
+                    }
+                    Err(err) => error_bar.show(&format!("Не удалось перенести задачи: {}", err)),
+                }
+            }
+        });
+
+        let report_template = screens.borrow().report_template.clone();
+        report_template.connect_changed({
+            let state = self.state.clone();
+            let screens = screens.clone();
+            move |_| {
+                // Also fires while the list is refilled, when the screens are already borrowed
+                if let Ok(screens) = screens.try_borrow() {
+                    screens.refresh_preview(&state.borrow());
+                }
+            }
+        });
+
+        let templates_btn = Button::with_label("✏️ Шаблоны");
+        templates_btn.set_tooltip_text(Some("Создать или изменить шаблон отчёта"));
+        templates_btn.connect_clicked({
+            let state = self.state.clone();
+            let screens = screens.clone();
+            let error_bar = self.error_bar.clone();
+            move |_| {
+                let start = screens.borrow().selected_template();
+                let Some(saved) = edit_template(&state.borrow(), &start) else {
+                    return;
+                };
+                let mut sc = screens.borrow_mut();
+                if let Err(err) = sc.refresh_templates() {
+                    error_bar.show(&format!("Не удалось загрузить шаблоны отчётов: {}", err));
+                }
+                sc.report_template.set_active_id(Some(&saved));
+                sc.refresh_preview(&state.borrow());
+            }
+        });
+
         let copy_btn = Button::with_label("📋 Копировать отчет");
         copy_btn.connect_clicked({
             let state = self.state.clone();
+            let screens = screens.clone();
             move |_| {
+                let template = screens.borrow().selected_template();
                 let s = state.borrow();
-                let report = s.generate_report(&s.cur_date);
+                let ctx = report::day_context(&s, &s.cur_date);
                 drop(s);
-
-                let clipboard = gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD);
-                clipboard.set_text(&report);
+                let report = match template.render(&ctx) {
+                    Ok(report) => report,
+                    Err(err) => {
+                        show_error(&err.to_string());
+                        return;
+                    }
+                };
+                copy_report(report, template.is_standard().then(|| report::html(&ctx)));
 
                 let dialog = gtk::MessageDialog::new(
                     Option::<&Window>::None,
@@ -354,9 +1030,33 @@ impl App {
             }
         });
 
+        let save_btn = Button::with_label("💾 Сохранить отчёт как…");
+        save_btn.connect_clicked({
+            let state = self.state.clone();
+            let screens = screens.clone();
+            move |_| {
+                let report = match screens.borrow().render_report(&state.borrow()) {
+                    Ok(report) => report,
+                    Err(err) => {
+                        show_error(&err.to_string());
+                        return;
+                    }
+                };
+                let s = state.borrow();
+                let ctx = report::day_context(&s, &s.cur_date);
+                let name = format!("Отчет {}", s.cur_date);
+                drop(s);
+                save_report_as(&name, &ctx, &report);
+            }
+        });
+
         btn_box.pack_start(&back_btn, false, false, 5);
         btn_box.pack_start(&add_btn, false, false, 5);
+        btn_box.pack_start(&carry_btn, false, false, 5);
+        btn_box.pack_start(&report_template, false, false, 5);
         btn_box.pack_start(&copy_btn, false, false, 5);
+        btn_box.pack_start(&save_btn, false, false, 5);
+        btn_box.pack_start(&templates_btn, false, false, 5);
         vbox.pack_start(&btn_box, false, false, 5);
 
         // Double-click to edit
@@ -381,7 +1081,7 @@ impl App {
         vbox.upcast()
     }
 
-    fn create_edit_screen(&self, screens: &Rc<RefCell<Screens>>) -> gtk::Widget {
+    fn create_edit_screen(&self, _screens: &Rc<RefCell<Screens>>) -> gtk::Widget {
         let vbox = GtkBox::new(Orientation::Vertical, 10);
         vbox.set_margin_top(20);
         vbox.set_margin_bottom(20);
@@ -407,12 +1107,13 @@ impl App {
         vbox.pack_start(&status_label, false, false, 5);
 
         let status_combo = gtk::ComboBoxText::new();
-        for status in Status::all() {
-            status_combo.append(Some(status.to_str()), status.to_str());
-        }
-        status_combo.set_active(Some(0));
+        fill_status_combo(&status_combo, &Status::initial());
         vbox.pack_start(&status_combo, false, false, 5);
 
+        let blocked_check = gtk::CheckButton::with_label("⛔ Заблокирована");
+        blocked_check.set_tooltip_text(Some("Задача ждёт чего-то извне и попадёт в блокеры отчёта для стендапа"));
+        vbox.pack_start(&blocked_check, false, false, 5);
+
         let date_label = Label::new(Some("Дата (ГГГГ-ММ-ДД):"));
         date_label.set_halign(Align::Start);
         vbox.pack_start(&date_label, false, false, 5);
@@ -421,6 +1122,15 @@ impl App {
         date_entry.set_text(&chrono::Local::now().format("%Y-%m-%d").to_string());
         vbox.pack_start(&date_entry, false, false, 5);
 
+        let history_expander = gtk::Expander::new(Some("🕓 История"));
+        let history_label = Label::new(None);
+        history_label.set_halign(Align::Start);
+        history_label.set_xalign(0.0);
+        history_label.set_line_wrap(true);
+        history_label.set_selectable(true);
+        history_expander.add(&history_label);
+        vbox.pack_start(&history_expander, false, false, 5);
+
         let btn_box = GtkBox::new(Orientation::Horizontal, 10);
         btn_box.set_halign(Align::Center);
         btn_box.set_margin_top(20);
@@ -438,42 +1148,31 @@ impl App {
             let state = self.state.clone();
             let stack = self.stack.clone();
             let edit_context = self.edit_context.clone();
+            let error_bar = self.error_bar.clone();
+            let history = self.history.clone();
+            let toast = self.toast.clone();
             move |_| {
                 // Get task info to delete
-                let task_info = {
-                    let ctx = edit_context.borrow();
-                    if let Some(ref ctx) = *ctx {
-                        if let Some(task_id) = ctx.task_id {
-                            Some((task_id, ctx.date.clone()))
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
+                let task_info = edit_context
+                    .borrow()
+                    .as_ref()
+                    .and_then(|ctx| ctx.task_id.map(|id| (id, ctx.date.clone())));
+                let Some((task_id, date)) = task_info else {
+                    return;
                 };
 
-                if let Some((task_id, date)) = task_info {
-                    let mut s = state.borrow_mut();
-                    let _ = s.delete_row(date, task_id);
-                    let _ = s.save();
-                    drop(s);
-
-                    let dialog = gtk::MessageDialog::new(
-                        Option::<&Window>::None,
-                        gtk::DialogFlags::MODAL,
-                        gtk::MessageType::Info,
-                        gtk::ButtonsType::Ok,
-                        "Задача удалена!"
-                    );
-                    dialog.run();
-                    dialog.close();
-
-                    // Clear context
-                    *edit_context.borrow_mut() = None;
-
-                    stack.set_visible_child_name(SCREEN_TASKS);
+                let command = Command::Delete { date, id: task_id };
+                let result = history.borrow_mut().execute(&mut state.borrow_mut(), command);
+                if let Err(err) = result.map(drop).or_else(report_journal) {
+                    error_bar.show(&format!("Не удалось удалить задачу: {}", err));
+                    return;
                 }
+
+                // Clear context
+                *edit_context.borrow_mut() = None;
+
+                stack.set_visible_child_name(SCREEN_TASKS);
+                toast.show("Задача удалена", history.borrow().version());
             }
         });
 
@@ -482,79 +1181,42 @@ impl App {
         let state = self.state.clone();
         let stack = self.stack.clone();
         let edit_context = self.edit_context.clone();
+        let error_bar = self.error_bar.clone();
+        let history = self.history.clone();
         let text_buffer_clone = text_buffer.clone();
         let status_combo_clone = status_combo.clone();
+        let blocked_check_clone = blocked_check.clone();
         let date_entry_clone = date_entry.clone();
         
         save_btn.connect_clicked(move |_| {
             let (start, end) = text_buffer_clone.bounds();
-            let text = text_buffer_clone.text(&start, &end, false).unwrap_or_default().trim().to_string();
-
-            if text.is_empty() {
-                let dialog = gtk::MessageDialog::new(
-                    Option::<&Window>::None,
-                    gtk::DialogFlags::MODAL,
-                    gtk::MessageType::Warning,
-                    gtk::ButtonsType::Ok,
-                    "Введите текст задачи!"
-                );
-                dialog.run();
-                dialog.close();
-                return;
-            }
-
-            let status_str = status_combo_clone.active_id().unwrap_or_else(|| "В работе".into());
-            let status = match status_str.as_str() {
-                "Открыто" => Status::Open,
-                "Отдал в тестирование" => Status::Testing,
-                "Передал в тестирование" => Status::Testing,
-                "Готово" => Status::Ready,
-                _ => Status::Working,
+            let text = text_buffer_clone.text(&start, &end, false).unwrap_or_default().to_string();
+
+            let status = status_combo_clone
+                .active_id()
+                .map_or_else(Status::initial, |id| Status::new(&id));
+            let blocked = blocked_check_clone.is_active();
+
+            let date = date_entry_clone.text().trim().to_string();
+            let change = match edit_context.borrow().as_ref().and_then(|ctx| ctx.task_id.map(|id| (id, ctx.date.clone()))) {
+                Some((id, old_date)) => RowChange::Update {
+                    date: old_date,
+                    id,
+                    text: Some(text),
+                    status: Some(status),
+                    blocked: Some(blocked),
+                    new_date: Some(date),
+                },
+                None => RowChange::Create { date, text, status, blocked },
             };
 
-            let date = date_entry_clone.text().to_string();
-            if date.len() != 10 || !date.chars().all(|c| c.is_digit(10) || c == '-') {
-                let dialog = gtk::MessageDialog::new(
-                    Option::<&Window>::None,
-                    gtk::DialogFlags::MODAL,
-                    gtk::MessageType::Warning,
-                    gtk::ButtonsType::Ok,
-                    "Неверный формат даты (используйте ГГГГ-ММ-ДД)!"
-                );
-                dialog.run();
-                dialog.close();
+            // Validated and saved as a whole, the form keeps its data on failure
+            let result = history.borrow_mut().execute(&mut state.borrow_mut(), Command::Apply(change));
+            if let Err(err) = result.map(drop).or_else(report_journal) {
+                error_bar.show(&format!("Не удалось сохранить задачу: {}", err));
                 return;
             }
-
-            let mut s = state.borrow_mut();
-            let ctx = edit_context.borrow();
-            
-            if let Some(ref ctx) = *ctx {
-                // Edit existing task
-                if let Some(task_id) = ctx.task_id {
-                    let old_date = ctx.date.clone();
-                    // Update text
-                    let _ = s.edit_row(old_date.clone(), task_id, text.clone());
-                    // Update status
-                    let _ = s.update_row_status(old_date.clone(), task_id, status);
-                    // Move to new date if changed
-                    if old_date != date {
-                        let _ = s.move_row(old_date, task_id, date.clone());
-                    }
-                }
-            } else {
-                // Create new task
-                let current_date = s.cur_date.clone();
-                s.cur_date = date.clone();
-                let _ = s.add_row(text.clone());
-                let new_id = s.max_id;
-                let _ = s.update_row_status(date.clone(), new_id, status);
-                s.cur_date = current_date;
-            }
-            
-            let _ = s.save();
-            drop(s);
-            drop(ctx);
+            error_bar.hide();
 
             let dialog = gtk::MessageDialog::new(
                 Option::<&Window>::None,
@@ -571,7 +1233,8 @@ impl App {
 
             // Clear form
             text_buffer_clone.set_text("");
-            status_combo_clone.set_active(Some(0));
+            fill_status_combo(&status_combo_clone, &Status::initial());
+            blocked_check_clone.set_active(false);
             date_entry_clone.set_text(&chrono::Local::now().format("%Y-%m-%d").to_string());
 
             stack.set_visible_child_name(SCREEN_TASKS);
@@ -587,16 +1250,1000 @@ impl App {
             header: header.clone(),
             text_buffer: text_buffer.clone(),
             status_combo: status_combo.clone(),
+            blocked_check: blocked_check.clone(),
             date_entry: date_entry.clone(),
             delete_btn: delete_btn.clone(),
+            history: history_expander.clone(),
+            history_label: history_label.clone(),
         });
 
         vbox.show_all();
         vbox.upcast()
     }
 
+    fn create_backups_screen(&self, screens: &Rc<RefCell<Screens>>) -> gtk::Widget {
+        let vbox = GtkBox::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(10);
+        vbox.set_margin_bottom(10);
+        vbox.set_margin_start(10);
+        vbox.set_margin_end(10);
+
+        let header = Label::new(None);
+        header.set_markup("<span size='large' weight='bold'>🗄️ Резервные копии</span>");
+        vbox.pack_start(&header, false, false, 5);
+
+        let scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scrolled.set_vexpand(true);
+
+        let tree_view = gtk::TreeView::with_model(&screens.borrow().backup_store);
+        for (title, column_id) in [("Создана", 1), ("Тип", 2), ("Задач", 3), ("Даты", 4)] {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.set_title(title);
+            gtk::prelude::CellLayoutExt::pack_start(&column, &renderer, true);
+            gtk::prelude::CellLayoutExt::add_attribute(&column, &renderer, "text", column_id);
+            tree_view.append_column(&column);
+        }
+
+        scrolled.add(&tree_view);
+        vbox.pack_start(&scrolled, true, true, 5);
+
+        let btn_box = GtkBox::new(Orientation::Horizontal, 10);
+        btn_box.set_halign(Align::Center);
+
+        let back_btn = Button::with_label("← Назад");
+        back_btn.connect_clicked({
+            let stack = self.stack.clone();
+            move |_| {
+                stack.set_visible_child_name(SCREEN_MAIN);
+            }
+        });
+
+        let preview_btn = Button::with_label("👁 Просмотр");
+        preview_btn.connect_clicked({
+            let tree_view = tree_view.clone();
+            let error_bar = self.error_bar.clone();
+            move |_| {
+                if let Some(snapshot) = selected_snapshot(&tree_view) {
+                    match snapshot.load() {
+                        Ok(snapshot_state) => show_snapshot_preview(&snapshot, &snapshot_state),
+                        Err(err) => error_bar.show(&format!("Не удалось открыть копию: {}", err)),
+                    }
+                }
+            }
+        });
+
+        let restore_btn = Button::with_label("↩️ Восстановить");
+        restore_btn.connect_clicked({
+            let state = self.state.clone();
+            let stack = self.stack.clone();
+            let tree_view = tree_view.clone();
+            let error_bar = self.error_bar.clone();
+            let history = self.history.clone();
+            let screens = screens.clone();
+            move |_| {
+                let Some(snapshot) = selected_snapshot(&tree_view) else {
+                    return;
+                };
+
+                let dialog = gtk::MessageDialog::new(
+                    Option::<&Window>::None,
+                    gtk::DialogFlags::MODAL,
+                    gtk::MessageType::Question,
+                    gtk::ButtonsType::YesNo,
+                    &format!("Восстановить копию от {}?", snapshot.created.format("%Y-%m-%d %H:%M:%S"))
+                );
+                dialog.set_secondary_text(Some("Текущие данные будут сохранены в новую резервную копию."));
+                let response = dialog.run();
+                dialog.close();
+                if response != gtk::ResponseType::Yes {
+                    return;
+                }
+
+                let result = snapshot.load().and_then(|restored| {
+                    state.borrow_mut().save_change(|s| {
+                        s.replace_data(restored);
+                        Ok(())
+                    })
+                });
+                match result {
+                    Ok(((), diffs)) => {
+                        if let Some(service) = &screens.borrow().service {
+                            service.announce(&diffs);
+                        }
+                        history.borrow_mut().clear();
+                        stack.set_visible_child_name(SCREEN_MAIN);
+                        if let Err(err) = journal::record(&diffs) {
+                            show_error(&err.to_string());
+                        }
+                    }
+                    Err(err) => error_bar.show(&format!("Не удалось восстановить данные: {}", err)),
+                }
+            }
+        });
+
+        btn_box.pack_start(&back_btn, false, false, 5);
+        btn_box.pack_start(&preview_btn, false, false, 5);
+        btn_box.pack_start(&restore_btn, false, false, 5);
+        vbox.pack_start(&btn_box, false, false, 5);
+
+        // Double-click to preview
+        tree_view.connect_row_activated(move |_, _, _| {
+            preview_btn.clicked();
+        });
+
+        vbox.show_all();
+        vbox.upcast()
+    }
+
+    /// The statistics screen: a summary and the charts, each with an export button.
+    fn create_stats_screen(&self, screens: &Rc<RefCell<Screens>>) -> gtk::Widget {
+        let vbox = GtkBox::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(10);
+        vbox.set_margin_bottom(10);
+        vbox.set_margin_start(10);
+        vbox.set_margin_end(10);
+
+        let header = Label::new(None);
+        header.set_markup("<span size='large' weight='bold'>📈 Статистика</span>");
+        vbox.pack_start(&header, false, false, 5);
+
+        let sc = screens.borrow();
+        vbox.pack_start(&sc.stats_summary, false, false, 5);
+
+        // Two charts a row, each with its own export button
+        let grid = &sc.stats_grid;
+        grid.set_row_spacing(10);
+        grid.set_column_spacing(10);
+        grid.set_row_homogeneous(true);
+        grid.set_column_homogeneous(true);
+        for (i, chart) in Chart::ALL.into_iter().enumerate() {
+            let area = gtk::DrawingArea::new();
+            area.set_size_request(320, 180);
+            area.set_hexpand(true);
+            area.set_vexpand(true);
+            area.connect_draw({
+                let stats = sc.stats.clone();
+                move |area, cr| {
+                    let (width, height) = (f64::from(area.allocated_width()), f64::from(area.allocated_height()));
+                    if let Err(err) = dashboard::draw(cr, chart, &stats.borrow(), width, height) {
+                        eprintln!("Failed to draw a chart: {}", err);
+                    }
+                    glib::Propagation::Stop
+                }
+            });
+
+            let export_btn = Button::with_label("💾 Экспорт…");
+            export_btn.set_tooltip_text(Some("Сохранить график в PNG или SVG"));
+            export_btn.set_halign(Align::End);
+            export_btn.connect_clicked({
+                let stats = sc.stats.clone();
+                let error_bar = self.error_bar.clone();
+                move |_| {
+                    let Some(path) = choose_chart_file(chart) else {
+                        return;
+                    };
+                    match dashboard::export(chart, &stats.borrow(), &path) {
+                        Ok(()) => error_bar.hide(),
+                        Err(err) => error_bar.show(&format!("Не удалось сохранить график: {}", err)),
+                    }
+                }
+            });
+
+            let cell = GtkBox::new(Orientation::Vertical, 5);
+            cell.pack_start(&area, true, true, 0);
+            cell.pack_start(&export_btn, false, false, 0);
+            grid.attach(&cell, (i % 2) as i32, (i / 2) as i32, 1, 1);
+        }
+        vbox.pack_start(grid, true, true, 5);
+
+        let back_btn = Button::with_label("← Назад");
+        back_btn.set_halign(Align::Center);
+        back_btn.connect_clicked({
+            let stack = self.stack.clone();
+            move |_| {
+                stack.set_visible_child_name(SCREEN_MAIN);
+            }
+        });
+        vbox.pack_start(&back_btn, false, false, 5);
+
+        vbox.show_all();
+        vbox.upcast()
+    }
+
+    fn create_search_screen(&self, screens: &Rc<RefCell<Screens>>) -> gtk::Widget {
+        let vbox = GtkBox::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(10);
+        vbox.set_margin_bottom(10);
+        vbox.set_margin_start(10);
+        vbox.set_margin_end(10);
+
+        let header = Label::new(None);
+        header.set_markup("<span size='large' weight='bold'>🔍 Поиск задач</span>");
+        vbox.pack_start(&header, false, false, 5);
+
+        let sc = screens.borrow();
+        sc.search_entry.set_placeholder_text(Some("Слова из текста задачи"));
+        sc.search_from.set_width_chars(10);
+        sc.search_from.set_placeholder_text(Some("ГГГГ-ММ-ДД"));
+        sc.search_to.set_width_chars(10);
+        sc.search_to.set_placeholder_text(Some("ГГГГ-ММ-ДД"));
+        let filter_box = GtkBox::new(Orientation::Horizontal, 10);
+        filter_box.pack_start(&sc.search_entry, true, true, 0);
+        filter_box.pack_start(&sc.search_status, false, false, 0);
+        filter_box.pack_start(&Label::new(Some("с")), false, false, 0);
+        filter_box.pack_start(&sc.search_from, false, false, 0);
+        filter_box.pack_start(&Label::new(Some("по")), false, false, 0);
+        filter_box.pack_start(&sc.search_to, false, false, 0);
+        vbox.pack_start(&filter_box, false, false, 5);
+
+        sc.search_summary.set_halign(Align::Start);
+        vbox.pack_start(&sc.search_summary, false, false, 0);
+
+        let scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scrolled.set_vexpand(true);
+        let tree_view = gtk::TreeView::with_model(&sc.search_store);
+        tree_view.set_headers_visible(false);
+
+        let date_renderer = gtk::CellRendererText::new();
+        let date_column = gtk::TreeViewColumn::new();
+        gtk::prelude::CellLayoutExt::pack_start(&date_column, &date_renderer, true);
+        gtk::prelude::CellLayoutExt::add_attribute(&date_column, &date_renderer, "text", 1);
+        tree_view.append_column(&date_column);
+
+        let status_renderer = gtk::CellRendererText::new();
+        let status_column = gtk::TreeViewColumn::new();
+        gtk::prelude::CellLayoutExt::pack_start(&status_column, &status_renderer, true);
+        gtk::prelude::CellLayoutExt::add_attribute(&status_column, &status_renderer, "text", 2);
+        gtk::prelude::CellLayoutExt::add_attribute(&status_column, &status_renderer, "foreground", 4);
+        tree_view.append_column(&status_column);
+
+        let snippet_renderer = gtk::CellRendererText::new();
+        snippet_renderer.set_property("ellipsize", pango::EllipsizeMode::End);
+        let snippet_column = gtk::TreeViewColumn::new();
+        snippet_column.set_expand(true);
+        gtk::prelude::CellLayoutExt::pack_start(&snippet_column, &snippet_renderer, true);
+        gtk::prelude::CellLayoutExt::add_attribute(&snippet_column, &snippet_renderer, "markup", 3);
+        tree_view.append_column(&snippet_column);
+
+        scrolled.add(&tree_view);
+        vbox.pack_start(&scrolled, true, true, 5);
+
+        // Search again whenever the query or a filter changes
+        let refresh = {
+            let state = self.state.clone();
+            let screens = screens.clone();
+            move || screens.borrow().refresh_search(&state.borrow())
+        };
+        sc.search_entry.connect_search_changed({
+            let refresh = refresh.clone();
+            move |_| refresh()
+        });
+        sc.search_status.connect_changed({
+            let refresh = refresh.clone();
+            move |_| refresh()
+        });
+        for entry in [&sc.search_from, &sc.search_to] {
+            let refresh = refresh.clone();
+            entry.connect_changed(move |_| refresh());
+        }
+        sc.search_entry.connect_stop_search({
+            let stack = self.stack.clone();
+            move |_| stack.set_visible_child_name(SCREEN_MAIN)
+        });
+
+        // Enter in the search field opens the first result
+        sc.search_entry.connect_activate({
+            let tree_view = tree_view.clone();
+            move |_| {
+                let first = gtk::TreePath::new_first();
+                if tree_view.model().and_then(|model| model.iter(&first)).is_some() {
+                    tree_view.row_activated(&first, &snippet_column);
+                }
+            }
+        });
+        drop(sc);
+
+        // Double-click opens the task on its day
+        tree_view.connect_row_activated({
+            let state = self.state.clone();
+            let stack = self.stack.clone();
+            let edit_context = self.edit_context.clone();
+            let search_store = screens.borrow().search_store.clone();
+            move |_, path, _| {
+                let Some(iter) = search_store.iter(path) else {
+                    return;
+                };
+                let id: u32 = search_store.value(&iter, 0).get().unwrap();
+                let date: String = search_store.value(&iter, 1).get().unwrap();
+                state.borrow_mut().cur_date = date;
+                *edit_context.borrow_mut() = Some(EditContext {
+                    task_id: Some(id),
+                    date: String::new(),
+                });
+                stack.set_visible_child_name(SCREEN_EDIT);
+            }
+        });
+
+        let back_btn = Button::with_label("← Назад");
+        back_btn.connect_clicked({
+            let stack = self.stack.clone();
+            move |_| stack.set_visible_child_name(SCREEN_MAIN)
+        });
+        let btn_box = GtkBox::new(Orientation::Horizontal, 10);
+        btn_box.set_halign(Align::Center);
+        btn_box.pack_start(&back_btn, false, false, 5);
+        vbox.pack_start(&btn_box, false, false, 5);
+
+        vbox.show_all();
+        vbox.upcast()
+    }
+
+    /// Carries unfinished tasks over to today, once a day, and says what moved.
+    fn carry_over_on_startup(&self) {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        if self.state.borrow().read_only || !first_launch_on(&today) {
+            return;
+        }
+        let Some(from) = self.state.borrow().previous_date(&today) else {
+            return;
+        };
+        let command = Command::CarryOver {
+            from: from.clone(),
+            to: today,
+            mode: self.config.carry_over.mode,
+        };
+        let result = self.history.borrow_mut().execute(&mut self.state.borrow_mut(), command);
+        match result {
+            Ok(rows) if rows.is_empty() => {}
+            Ok(rows) => {
+                let dialog = gtk::MessageDialog::new(
+                    Option::<&Window>::None,
+                    gtk::DialogFlags::MODAL,
+                    gtk::MessageType::Info,
+                    gtk::ButtonsType::Ok,
+                    &format!("Незавершённые задачи за {} перенесены на сегодня: {}", from, rows.len())
+                );
+                let list: Vec<String> = rows
+                    .iter()
+                    .map(|row| format!("• {} ({})", row.text, row.status.label()))
+                    .collect();
+                dialog.set_secondary_text(Some(&list.join("\n")));
+                dialog.run();
+                dialog.close();
+            }
+            Err(err @ ReporterError::Journal(_)) => show_error(&err.to_string()),
+            Err(err) => self.error_bar.show(&format!("Не удалось перенести незавершённые задачи: {}", err)),
+        }
+    }
+
     pub fn run(&self) {
         self.window.show_all();
         gtk::main();
     }
 }
+
+const RESPONSE_RESTORE: u16 = 1;
+const RESPONSE_READ_ONLY: u16 = 2;
+const RESPONSE_START_FRESH: u16 = 3;
+
+/// Only the primary instance may upgrade, repair or restore the data file.
+fn load_state(primary: bool) -> State {
+    if !primary {
+        // Another window owns the file, saving from here would drop its changes
+        return match State::peek() {
+            Ok(state) => state,
+            Err(err) if err.is_not_found() => State::read_only(),
+            Err(err) => {
+                show_error(&format!("Не удалось прочитать файл с задачами: {}", err));
+                State::read_only()
+            }
+        };
+    }
+    match State::load() {
+        Ok(state) => state,
+        Err(err) if err.is_not_found() => State::new(),
+        // A newer app wrote this file: it is fine, just not ours to overwrite
+        Err(err @ ReporterError::UnsupportedSchema { .. }) => {
+            show_error(&err.to_string());
+            State::read_only()
+        }
+        Err(err) => recover_state(err),
+    }
+}
+
+/// Every way out but read only writes the file, so this is for the primary instance.
+fn recover_state(err: ReporterError) -> State {
+    let details = match State::quarantine() {
+        Ok(path) => format!("{}\n\nКопия файла сохранена в {}", err, path.display()),
+        Err(copy_err) => format!("{}\n\nНе удалось сохранить копию файла: {}", err, copy_err),
+    };
+
+    let dialog = gtk::MessageDialog::new(
+        Option::<&Window>::None,
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Error,
+        gtk::ButtonsType::None,
+        "Не удалось прочитать файл с задачами"
+    );
+    dialog.set_secondary_text(Some(&details));
+    dialog.add_button("Восстановить из резервной копии", gtk::ResponseType::Other(RESPONSE_RESTORE));
+    dialog.add_button("Только чтение", gtk::ResponseType::Other(RESPONSE_READ_ONLY));
+    dialog.add_button("Начать заново", gtk::ResponseType::Other(RESPONSE_START_FRESH));
+
+    let state = loop {
+        match dialog.run() {
+            gtk::ResponseType::Other(RESPONSE_RESTORE) => {
+                if let Some(state) = restore_from_backup() {
+                    break state;
+                }
+            }
+            gtk::ResponseType::Other(RESPONSE_START_FRESH) => break State::new(),
+            // Closing the dialog must not lead to the broken file being overwritten
+            _ => break State::read_only(),
+        }
+    };
+    dialog.close();
+    state
+}
+
+fn restore_from_backup() -> Option<State> {
+    let chooser = gtk::FileChooserDialog::with_buttons(
+        Some("Выберите резервную копию"),
+        Option::<&Window>::None,
+        gtk::FileChooserAction::Open,
+        &[("Отмена", gtk::ResponseType::Cancel), ("Открыть", gtk::ResponseType::Accept)],
+    );
+    if let Ok(state_file) = paths::state_file() {
+        let backups = backup::backup_dir(&state_file);
+        if backups.is_dir() {
+            chooser.set_current_folder(backups);
+        } else if let Some(dir) = state_file.parent() {
+            chooser.set_current_folder(dir);
+        }
+    }
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("JSON"));
+    filter.add_pattern("*.json");
+    chooser.add_filter(filter);
+
+    let response = chooser.run();
+    let path = chooser.filename();
+    chooser.close();
+    let path = path.filter(|_| response == gtk::ResponseType::Accept)?;
+
+    let result = State::load_from(&path).and_then(|mut state| state.save().map(|_| state));
+    match result {
+        Ok(state) => Some(state),
+        Err(err) => {
+            show_error(&format!("Не удалось восстановить данные: {}", err));
+            None
+        }
+    }
+}
+
+fn fill_backup_store(backup_store: &gtk::ListStore) {
+    backup_store.clear();
+    let snapshots = match paths::state_file().and_then(|path| backup::list_snapshots(&path)) {
+        Ok(snapshots) => snapshots,
+        Err(err) => {
+            show_error(&format!("Не удалось прочитать резервные копии: {}", err));
+            return;
+        }
+    };
+    for snapshot in snapshots {
+        let (task_count, dates) = match snapshot.load() {
+            Ok(s) => {
+                let dates = match s.date_range() {
+                    Some((first, last)) if first == last => first,
+                    Some((first, last)) => format!("{} — {}", first, last),
+                    None => "—".to_string(),
+                };
+                (s.task_count() as u32, dates)
+            }
+            Err(_) => (0, "повреждена".to_string()),
+        };
+        let iter = backup_store.append();
+        backup_store.set(&iter, &[
+            (0, &snapshot.path.to_string_lossy().to_string()),
+            (1, &snapshot.created.format("%Y-%m-%d %H:%M:%S").to_string()),
+            (2, &snapshot.kind.to_str()),
+            (3, &task_count),
+            (4, &dates),
+        ]);
+    }
+}
+
+fn selected_snapshot(tree_view: &gtk::TreeView) -> Option<Snapshot> {
+    let (model, iter) = tree_view.selection().selected()?;
+    let path: String = model.value(&iter, 0).get().ok()?;
+    paths::state_file()
+        .and_then(|path| backup::list_snapshots(&path))
+        .ok()?
+        .into_iter()
+        .find(|snapshot| snapshot.path.to_string_lossy() == path)
+}
+
+fn show_snapshot_preview(snapshot: &Snapshot, snapshot_state: &State) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&format!("Копия от {}", snapshot.created.format("%Y-%m-%d %H:%M:%S"))),
+        Option::<&Window>::None,
+        gtk::DialogFlags::MODAL,
+        &[("Закрыть", gtk::ResponseType::Close)],
+    );
+    dialog.set_default_size(600, 500);
+
+    let reports: Vec<String> = snapshot_state
+        .get_all_dates()
+        .iter()
+        .map(|date| snapshot_state.generate_report(date))
+        .collect();
+
+    let text_view = TextView::new();
+    text_view.set_editable(false);
+    text_view.set_wrap_mode(gtk::WrapMode::Word);
+    text_view.buffer().unwrap().set_text(&reports.join("\n"));
+
+    let scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scrolled.set_vexpand(true);
+    scrolled.add(&text_view);
+    dialog.content_area().pack_start(&scrolled, true, true, 5);
+    dialog.show_all();
+
+    dialog.run();
+    dialog.close();
+}
+
+const CLIPBOARD_TEXT: u32 = 0;
+const CLIPBOARD_HTML: u32 = 1;
+
+/// Puts the report on the clipboard as plain text and, when there is an HTML
+/// version of the same layout, as HTML too, so that mail clients and wikis
+/// keep the headings and lists.
+fn copy_report(text: String, html: Option<String>) {
+    let mut targets = Vec::new();
+    if html.is_some() {
+        targets.push(gtk::TargetEntry::new("text/html", gtk::TargetFlags::empty(), CLIPBOARD_HTML));
+    }
+    targets.extend([
+        gtk::TargetEntry::new("UTF8_STRING", gtk::TargetFlags::empty(), CLIPBOARD_TEXT),
+        gtk::TargetEntry::new("text/plain;charset=utf-8", gtk::TargetFlags::empty(), CLIPBOARD_TEXT),
+        gtk::TargetEntry::new("text/plain", gtk::TargetFlags::empty(), CLIPBOARD_TEXT),
+        gtk::TargetEntry::new("STRING", gtk::TargetFlags::empty(), CLIPBOARD_TEXT),
+    ]);
+    let clipboard = gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD);
+    clipboard.set_with_data(&targets, move |_, selection, info| match &html {
+        Some(html) if info == CLIPBOARD_HTML => selection.set(&selection.target(), 8, html.as_bytes()),
+        _ => {
+            selection.set_text(&text);
+        }
+    });
+}
+
+/// Asks where to save the report; the extension picks the format. Returns
+/// whether it was saved.
+fn save_report_as(name: &str, ctx: &template::Context, text: &str) -> bool {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some("Сохранить отчёт"),
+        Option::<&Window>::None,
+        gtk::FileChooserAction::Save,
+        &[("Отмена", gtk::ResponseType::Cancel), ("Сохранить", gtk::ResponseType::Accept)],
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name(&format!("{}.{}", name, report::Format::Text.extension()));
+    let formats = [
+        ("Текст (*.txt)", report::Format::Text),
+        ("Markdown (*.md)", report::Format::Markdown),
+        ("HTML (*.html)", report::Format::Html),
+    ];
+    let filters: Vec<(gtk::FileFilter, report::Format)> = formats
+        .into_iter()
+        .map(|(title, format)| {
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some(title));
+            filter.add_pattern(&format!("*.{}", format.extension()));
+            dialog.add_filter(filter.clone());
+            (filter, format)
+        })
+        .collect();
+
+    let mut saved = false;
+    if dialog.run() == gtk::ResponseType::Accept
+        && let Some(mut path) = dialog.filename()
+    {
+        // The chosen filter decides the format when the name has no extension
+        if path.extension().is_none() {
+            let chosen = dialog.filter();
+            let format = filters
+                .iter()
+                .find(|(filter, _)| Some(filter) == chosen.as_ref())
+                .map_or(report::Format::Text, |(_, format)| *format);
+            path.set_extension(format.extension());
+        }
+        match report::save(&path, ctx, text) {
+            Ok(()) => saved = true,
+            Err(err) => show_error(&format!("Не удалось сохранить отчёт: {}", err)),
+        }
+    }
+    dialog.close();
+    saved
+}
+
+const RESPONSE_COPY: gtk::ResponseType = gtk::ResponseType::Other(1);
+const RESPONSE_SAVE: gtk::ResponseType = gtk::ResponseType::Other(2);
+
+/// Report over several days, for a preset period or any two dates, laid out by
+/// one of the report templates.
+fn range_report_dialog(state: State, templates: Vec<Template>, config: &Config) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Отчёт за период"),
+        Option::<&Window>::None,
+        gtk::DialogFlags::MODAL,
+        &[
+            ("Копировать", RESPONSE_COPY),
+            ("Сохранить как…", RESPONSE_SAVE),
+            ("Закрыть", gtk::ResponseType::Close),
+        ],
+    );
+    dialog.set_default_size(700, 600);
+
+    let preset_combo = gtk::ComboBoxText::new();
+    for (i, preset) in report::RangePreset::ALL.iter().enumerate() {
+        preset_combo.append(Some(&i.to_string()), preset.title());
+    }
+    let from_entry = Entry::new();
+    from_entry.set_width_chars(10);
+    let to_entry = Entry::new();
+    to_entry.set_width_chars(10);
+
+    let grouping_combo = gtk::ComboBoxText::new();
+    grouping_combo.append(Some("by-day"), "По статусам и дням");
+    grouping_combo.append(Some("merged"), "Без повторов");
+    grouping_combo.set_active_id(Some("by-day"));
+
+    let template_combo = gtk::ComboBoxText::new();
+    for template in &templates {
+        template_combo.append(Some(&template.name), &template.name);
+    }
+    template_combo.set_active_id(Some(template::STANDARD));
+
+    let period_box = GtkBox::new(Orientation::Horizontal, 10);
+    period_box.pack_start(&preset_combo, false, false, 0);
+    period_box.pack_start(&Label::new(Some("с")), false, false, 0);
+    period_box.pack_start(&from_entry, false, false, 0);
+    period_box.pack_start(&Label::new(Some("по")), false, false, 0);
+    period_box.pack_start(&to_entry, false, false, 0);
+    let layout_box = GtkBox::new(Orientation::Horizontal, 10);
+    layout_box.pack_start(&grouping_combo, false, false, 0);
+    layout_box.pack_start(&template_combo, false, false, 0);
+
+    let preview_view = TextView::new();
+    preview_view.set_editable(false);
+    preview_view.set_monospace(true);
+    preview_view.set_wrap_mode(gtk::WrapMode::Word);
+    let preview = preview_view.buffer().unwrap();
+    let scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scrolled.set_vexpand(true);
+    scrolled.add(&preview_view);
+
+    // The report as the fields currently describe it
+    let build = {
+        let from_entry = from_entry.clone();
+        let to_entry = to_entry.clone();
+        let grouping_combo = grouping_combo.clone();
+        let template_combo = template_combo.clone();
+        move || -> Result<(Template, template::Context, String), ReporterError> {
+            let grouping = match grouping_combo.active_id().as_deref() {
+                Some("merged") => report::RangeGrouping::Merged,
+                _ => report::RangeGrouping::ByDay,
+            };
+            let ctx = report::range_context(&state, from_entry.text().trim(), to_entry.text().trim(), grouping)?;
+            let name = template_combo.active_id();
+            let template = templates
+                .iter()
+                .find(|template| Some(template.name.as_str()) == name.as_deref())
+                .cloned()
+                .unwrap_or_else(Template::standard);
+            let text = template.render(&ctx)?;
+            Ok((template, ctx, text))
+        }
+    };
+    let build = Rc::new(build);
+    let update_preview = {
+        let build = build.clone();
+        let preview = preview.clone();
+        move || preview.set_text(&build().map_or_else(|err| err.to_string(), |(_, _, text)| text))
+    };
+
+    preset_combo.connect_changed({
+        let from_entry = from_entry.clone();
+        let to_entry = to_entry.clone();
+        let sprint = config.sprint.clone();
+        move |combo| {
+            let Some(preset) = combo
+                .active_id()
+                .and_then(|id| id.parse::<usize>().ok())
+                .and_then(|i| report::RangePreset::ALL.get(i).copied())
+            else {
+                return;
+            };
+            let (from, to) = preset.range(chrono::Local::now().date_naive(), &sprint);
+            from_entry.set_text(&from.format("%Y-%m-%d").to_string());
+            to_entry.set_text(&to.format("%Y-%m-%d").to_string());
+        }
+    });
+    for entry in [&from_entry, &to_entry] {
+        let update_preview = update_preview.clone();
+        entry.connect_changed(move |_| update_preview());
+    }
+    for combo in [&grouping_combo, &template_combo] {
+        let update_preview = update_preview.clone();
+        combo.connect_changed(move |_| update_preview());
+    }
+    preset_combo.set_active(Some(0));
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.pack_start(&period_box, false, false, 5);
+    content.pack_start(&layout_box, false, false, 0);
+    content.pack_start(&scrolled, true, true, 5);
+    dialog.show_all();
+
+    loop {
+        let response = dialog.run();
+        if response != RESPONSE_COPY && response != RESPONSE_SAVE {
+            break;
+        }
+        let (template, ctx, text) = match build() {
+            Ok(report) => report,
+            Err(err) => {
+                show_error(&err.to_string());
+                continue;
+            }
+        };
+        if response == RESPONSE_COPY {
+            copy_report(text, template.is_standard().then(|| report::html(&ctx)));
+            show_info("Отчет скопирован в буфер обмена!");
+            break;
+        }
+        let name = format!("Отчет {} — {}", from_entry.text().trim(), to_entry.text().trim());
+        if save_report_as(&name, &ctx, &text) {
+            break;
+        }
+    }
+    dialog.close();
+}
+
+/// Asks where to export `chart`. The file gets `.png` unless `.svg` was asked for.
+fn choose_chart_file(chart: Chart) -> Option<std::path::PathBuf> {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(chart.title()),
+        Option::<&Window>::None,
+        gtk::FileChooserAction::Save,
+        &[("Отмена", gtk::ResponseType::Cancel), ("Сохранить", gtk::ResponseType::Accept)],
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name(&format!("{}.png", chart.file_stem()));
+    for (title, pattern) in [("PNG (*.png)", "*.png"), ("SVG (*.svg)", "*.svg")] {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(title));
+        filter.add_pattern(pattern);
+        dialog.add_filter(filter);
+    }
+    let path = match dialog.run() {
+        gtk::ResponseType::Accept => dialog.filename(),
+        _ => None,
+    };
+    dialog.close();
+    path.map(|mut path| {
+        if path.extension().is_none() {
+            path.set_extension("png");
+        }
+        path
+    })
+}
+
+/// Lets the user write a template, starting from `start`, with a preview of the
+/// current day's report. Returns the name it was saved under.
+fn edit_template(state: &State, start: &Template) -> Option<String> {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Шаблон отчёта"),
+        Option::<&Window>::None,
+        gtk::DialogFlags::MODAL,
+        &[("Отмена", gtk::ResponseType::Cancel), ("Сохранить", gtk::ResponseType::Accept)],
+    );
+    dialog.set_default_size(900, 550);
+
+    let name_entry = Entry::new();
+    // Built-in templates can't be overwritten, so their copies need a new name
+    if start.builtin {
+        name_entry.set_text(&format!("{} (копия)", start.name));
+    } else {
+        name_entry.set_text(&start.name);
+    }
+    let name_box = GtkBox::new(Orientation::Horizontal, 10);
+    name_box.pack_start(&Label::new(Some("Название:")), false, false, 0);
+    name_box.pack_start(&name_entry, true, true, 0);
+
+    let hint = Label::new(Some(
+        "{{date}}, {{count}} — дата и число задач; {{#statuses}}…{{/statuses}} — по статусам: \
+         {{label}}, {{id}}, {{count}}; {{#rows}}…{{/rows}} — по задачам статуса: {{text}}, {{number}}, {{id}}; \
+         {{^last}}…{{/last}} — кроме последнего; {{^statuses}}…{{/statuses}} — если задач нет.",
+    ));
+    hint.set_line_wrap(true);
+    hint.set_xalign(0.0);
+
+    let source_view = TextView::new();
+    source_view.set_monospace(true);
+    let source = source_view.buffer().unwrap();
+    source.set_text(&start.source);
+
+    let preview_view = TextView::new();
+    preview_view.set_editable(false);
+    preview_view.set_monospace(true);
+    preview_view.set_wrap_mode(gtk::WrapMode::Word);
+    let preview = preview_view.buffer().unwrap();
+
+    let ctx = report::day_context(state, &state.cur_date);
+    let update_preview = {
+        let preview = preview.clone();
+        move |source: &gtk::TextBuffer| {
+            let (start, end) = source.bounds();
+            let text = source.text(&start, &end, false).unwrap_or_default();
+            let rendered = Template::new("", &text).render(&ctx).unwrap_or_else(|err| err.to_string());
+            preview.set_text(&rendered);
+        }
+    };
+    update_preview(&source);
+    source.connect_changed(update_preview);
+
+    let framed = |title: &str, view: &TextView| {
+        let scrolled = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scrolled.add(view);
+        let frame = gtk::Frame::new(Some(title));
+        frame.add(&scrolled);
+        frame
+    };
+    let panes = gtk::Paned::new(Orientation::Horizontal);
+    panes.pack1(&framed("Шаблон", &source_view), true, false);
+    panes.pack2(&framed("Предпросмотр", &preview_view), true, false);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.pack_start(&name_box, false, false, 5);
+    content.pack_start(&hint, false, false, 0);
+    content.pack_start(&panes, true, true, 5);
+    dialog.show_all();
+
+    // Ask again until it's saved or given up
+    let saved = loop {
+        if dialog.run() != gtk::ResponseType::Accept {
+            break None;
+        }
+        let (start, end) = source.bounds();
+        let text = source.text(&start, &end, false).unwrap_or_default();
+        let name = name_entry.text().trim().to_string();
+        match template::save(&name, &text) {
+            Ok(_) => break Some(name),
+            Err(err) => show_error(&format!("Не удалось сохранить шаблон: {}", err)),
+        }
+    };
+    dialog.close();
+    saved
+}
+
+/// Lists the configured statuses and selects `current`, adding it if the config no longer has it.
+fn fill_status_combo(combo: &gtk::ComboBoxText, current: &Status) {
+    combo.remove_all();
+    for status in Status::all() {
+        combo.append(Some(status.id()), status.label());
+    }
+    if !combo.set_active_id(Some(current.id())) {
+        combo.append(Some(current.id()), current.label());
+        combo.set_active_id(Some(current.id()));
+    }
+}
+
+/// The status as the task lists show it, marked if the task is blocked.
+fn status_text(row: &Row) -> String {
+    if row.blocked {
+        format!("⛔ {}", row.status.label())
+    } else {
+        row.status.label().to_string()
+    }
+}
+
+/// The snippet as Pango markup, with the matches highlighted.
+fn snippet_markup(snippet: &search::Snippet) -> String {
+    let mut markup = String::new();
+    let mut end = 0;
+    for range in &snippet.matches {
+        markup.push_str(&glib::markup_escape_text(&snippet.text[end..range.start]));
+        markup.push_str(&format!(
+            "<span weight='bold' background='#f9f06b' foreground='#000000'>{}</span>",
+            glib::markup_escape_text(&snippet.text[range.clone()])
+        ));
+        end = range.end;
+    }
+    markup.push_str(&glib::markup_escape_text(&snippet.text[end..]));
+    markup
+}
+
+/// Whether this is the first launch on `today`, remembering it for the next one.
+fn first_launch_on(today: &str) -> bool {
+    let Ok(path) = paths::last_launch_file() else {
+        return false;
+    };
+    if std::fs::read_to_string(&path).is_ok_and(|last| last.trim() == today) {
+        return false;
+    }
+    if let Err(err) = std::fs::write(&path, today) {
+        eprintln!("Failed to remember the launch date: {}", err);
+    }
+    true
+}
+
+/// "5 мин назад" style age of a timestamp, a plain date once it's a month old.
+fn relative_time(at: chrono::DateTime<chrono::Local>) -> String {
+    let minutes = (chrono::Local::now() - at).num_minutes();
+    match minutes {
+        m if m < 1 => "только что".to_string(),
+        m if m < 60 => format!("{} мин назад", m),
+        m if m < 60 * 24 => format!("{} ч назад", m / 60),
+        m if m < 60 * 24 * 30 => format!("{} дн. назад", m / (60 * 24)),
+        _ => at.format("%Y-%m-%d").to_string(),
+    }
+}
+
+fn task_timeline(ids: &[u32]) -> String {
+    match paths::journal_file().and_then(|path| journal::task_history(&path, ids)) {
+        Ok(entries) if entries.is_empty() => "Изменений пока не записано".to_string(),
+        Ok(entries) => journal::timeline(&entries).join("\n"),
+        Err(err) => format!("Не удалось прочитать журнал: {}", err),
+    }
+}
+
+fn read_only_banner(message: &str) -> gtk::InfoBar {
+    let banner = gtk::InfoBar::new();
+    banner.set_message_type(gtk::MessageType::Warning);
+    let label = Label::new(Some(message));
+    label.set_line_wrap(true);
+    banner.content_area().add(&label);
+    banner
+}
+
+/// Journal failures come after the change is saved, so they are only shown
+/// and the change goes on as done. Any other error is handed back.
+fn report_journal(err: ReporterError) -> Result<(), ReporterError> {
+    match err {
+        ReporterError::Journal(_) => {
+            show_error(&err.to_string());
+            Ok(())
+        }
+        err => Err(err),
+    }
+}
+
+pub fn show_error(message: &str) {
+    let dialog = gtk::MessageDialog::new(
+        Option::<&Window>::None,
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Error,
+        gtk::ButtonsType::Ok,
+        message
+    );
+    dialog.run();
+    dialog.close();
+}
+
+pub fn show_info(message: &str) {
+    let dialog = gtk::MessageDialog::new(
+        Option::<&Window>::None,
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Info,
+        gtk::ButtonsType::Ok,
+        message
+    );
+    dialog.run();
+    dialog.close();
+}
@@ -0,0 +1,174 @@
+use gtk::gio;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk_reporter_core::merge::{self, Conflict, Placed, Side};
+use gtk_reporter_core::state::{RowDiff, State, diff_rows};
+use gtk_reporter_core::storage::{self, Storage};
+
+use crate::ui::show_error;
+
+// Editors and sync tools often write a file in several steps
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+type OnReload = Box<dyn Fn(&[RowDiff])>;
+
+struct Watcher {
+    storage: Box<dyn Storage>,
+    state: Rc<RefCell<State>>,
+    // What the file held when we last looked at it, the common ancestor for merges
+    base: RefCell<State>,
+    on_reload: OnReload,
+    pending: RefCell<Option<glib::SourceId>>,
+    // Set while the conflict dialog runs its own main loop
+    busy: Cell<bool>,
+}
+
+/// Watches the data file and brings outside changes into `state`.
+/// `on_reload` is called with the changed tasks after `state` was updated from disk.
+pub fn watch_state_file<F>(state: Rc<RefCell<State>>, on_reload: F) -> Result<gio::FileMonitor, String>
+where
+    F: Fn(&[RowDiff]) + 'static,
+{
+    let storage = storage::open().map_err(|err| err.to_string())?;
+    let monitor = gio::File::for_path(storage.path())
+        .monitor_file(gio::FileMonitorFlags::NONE, None::<&gio::Cancellable>)
+        .map_err(|err| err.to_string())?;
+
+    let base = RefCell::new(state.borrow().clone());
+    let watcher = Rc::new(Watcher {
+        storage,
+        state,
+        base,
+        on_reload: Box::new(on_reload),
+        pending: RefCell::new(None),
+        busy: Cell::new(false),
+    });
+    monitor.connect_changed(move |_, _, _, _| schedule_sync(&watcher));
+
+    Ok(monitor)
+}
+
+fn schedule_sync(watcher: &Rc<Watcher>) {
+    if let Some(source) = watcher.pending.borrow_mut().take() {
+        source.remove();
+    }
+    let source = glib::timeout_add_local_once(SETTLE_DELAY, {
+        let watcher = watcher.clone();
+        move || {
+            watcher.pending.borrow_mut().take();
+            if watcher.busy.get() {
+                schedule_sync(&watcher);
+            } else {
+                watcher.busy.set(true);
+                sync_from_disk(&watcher);
+                watcher.busy.set(false);
+            }
+        }
+    });
+    *watcher.pending.borrow_mut() = Some(source);
+}
+
+fn sync_from_disk(watcher: &Watcher) {
+    let Watcher { storage, state, base, on_reload, .. } = watcher;
+    let path = storage.path();
+    // Only the window holding the lock may write the upgraded file back
+    let loaded = if state.borrow().read_only { storage.peek() } else { storage.load() };
+    let remote = match loaded {
+        Ok(remote) => remote,
+        Err(err) => {
+            // A file moved away to be replaced comes back with the next event
+            if !err.is_not_found() {
+                show_error(&format!("Не удалось перечитать {}: {}", path.display(), err));
+            }
+            return;
+        }
+    };
+
+    // Our own save, or a write that changed nothing
+    if remote.same_data(&state.borrow()) {
+        *base.borrow_mut() = remote;
+        return;
+    }
+
+    let has_local_changes = !state.borrow().same_data(&base.borrow());
+    if !has_local_changes {
+        let mut s = state.borrow_mut();
+        let diffs = diff_rows(&s, &remote);
+        s.replace_data(remote.clone());
+        s.mark_persisted();
+        drop(s);
+        *base.borrow_mut() = remote;
+        on_reload(&diffs);
+        return;
+    }
+
+    let merged = merge::merge(&base.borrow(), &state.borrow(), &remote);
+    let merged = if merged.conflicts.is_empty() {
+        merged.state
+    } else {
+        let asked = state.borrow().clone();
+        let side = ask_conflict_side(path, &merged.conflicts);
+        // D-Bus and HTTP requests keep being served while the dialog is open
+        if state.borrow().same_data(&asked) {
+            merged.resolve(side)
+        } else {
+            merge::merge(&base.borrow(), &state.borrow(), &remote).resolve(side)
+        }
+    };
+    *base.borrow_mut() = remote;
+
+    let mut s = state.borrow_mut();
+    let diffs = diff_rows(&s, &merged);
+    s.replace_data(merged);
+    let result = if s.read_only { Ok(()) } else { s.save() };
+    drop(s);
+    on_reload(&diffs);
+    if let Err(err) = result {
+        show_error(&format!("Не удалось сохранить объединённые данные: {}", err));
+    }
+}
+
+fn describe(placed: &Option<Placed>) -> String {
+    match placed {
+        Some((date, row)) => format!("«{}» — {}, {}", row.text, row.status.label(), date),
+        None => "удалена".to_string(),
+    }
+}
+
+fn ask_conflict_side(path: &Path, conflicts: &[Conflict]) -> Side {
+    let details: Vec<String> = conflicts
+        .iter()
+        .map(|c| {
+            format!(
+                "Задача #{}\n  было: {}\n  здесь: {}\n  в файле: {}",
+                c.id,
+                describe(&c.base),
+                describe(&c.local),
+                describe(&c.remote)
+            )
+        })
+        .collect();
+
+    let dialog = gtk::MessageDialog::new(
+        Option::<&gtk::Window>::None,
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Warning,
+        gtk::ButtonsType::None,
+        &format!("Файл {} изменён извне, и часть задач изменена в обоих местах", path.display())
+    );
+    dialog.set_secondary_text(Some(&details.join("\n\n")));
+    dialog.add_button("Оставить мои", gtk::ResponseType::Reject);
+    dialog.add_button("Взять из файла", gtk::ResponseType::Accept);
+
+    let response = dialog.run();
+    dialog.close();
+    if response == gtk::ResponseType::Accept {
+        Side::Remote
+    } else {
+        Side::Local
+    }
+}